@@ -0,0 +1,2 @@
+//! The headless simulation: everything needed to set up and step a `World` without a window.
+pub mod physics;
@@ -1,10 +1,23 @@
 use ggez::GameResult;
-use crate::physics::laws::InteractionLawType;
+use rusticles::physics;
+use crate::physics::scenario::Scenario;
 use crate::rendering::simulation;
 
-mod physics;
 mod rendering;
 
 pub fn main() -> GameResult {
-    simulation::run(500, InteractionLawType::ImpulseCollision)
+    // Pick a scenario by name, e.g. `cargo run -- charged-gas`.
+    let name = std::env::args().nth(1).unwrap_or_else(|| String::from("ideal-gas"));
+    let scenario = Scenario::by_name(&name, 500).unwrap_or_else(|| {
+        eprintln!("Unknown scenario '{}', falling back to ideal-gas.", name);
+        Scenario::ideal_gas(500)
+    });
+    // Seed the run from the second argument, e.g. `cargo run -- charged-gas 42`, or at random.
+    // The seed is printed so a run can be repeated.
+    let seed = std::env::args().nth(2).and_then(|arg| arg.parse().ok()).unwrap_or_else(rand::random);
+    eprintln!("Seed: {}", seed);
+    // Step every frame by a fixed time in seconds from the third argument, e.g.
+    // `cargo run -- charged-gas 42 0.016`, instead of by the frame time.
+    let fixed_dt = std::env::args().nth(3).and_then(|arg| arg.parse().ok());
+    simulation::run(Scenario { seed, ..scenario }, fixed_dt)
 }
@@ -0,0 +1,274 @@
+use mint::Vector2;
+use crate::physics::laws::CoulombLaw;
+use crate::physics::particles::Particle;
+
+
+/// Depth at which cells stop splitting, so coincident particles share a leaf instead of
+/// recursing forever.
+const MAX_DEPTH: usize = 32;
+
+/// A square cell of the quadtree.
+struct Node {
+    /// Lower corner of the cell.
+    min: Vector2<f32>,
+    /// Side length of the cell.
+    size: f32,
+    /// Net charge of the particles inside the cell.
+    charge: f32,
+    /// Centre of charge, weighted by `|q|` so that neutral cells still have a sensible centre.
+    centre: Vector2<f32>,
+    /// Indices of the four children, or `None` for a leaf.
+    children: Option<[usize; 4]>,
+    /// Particles stored in a leaf.
+    particles: Vec<usize>,
+}
+
+impl Node {
+    fn contains(&self, position: Vector2<f32>) -> bool {
+        position.x >= self.min.x
+            && position.x <= self.min.x + self.size
+            && position.y >= self.min.y
+            && position.y <= self.min.y + self.size
+    }
+}
+
+/// A quadtree over the particles, storing the net charge of every cell.
+struct QuadTree {
+    nodes: Vec<Node>,
+}
+
+impl QuadTree {
+    fn new(particles: &[Particle]) -> QuadTree {
+        let mut min = Vector2 { x: f32::MAX, y: f32::MAX };
+        let mut max = Vector2 { x: f32::MIN, y: f32::MIN };
+        for particle in particles {
+            min.x = min.x.min(particle.position.x);
+            min.y = min.y.min(particle.position.y);
+            max.x = max.x.max(particle.position.x);
+            max.y = max.y.max(particle.position.y);
+        }
+        let size = (max.x - min.x).max(max.y - min.y).max(f32::EPSILON);
+
+        let mut tree = QuadTree { nodes: Vec::new() };
+        let indices: Vec<usize> = (0..particles.len()).collect();
+        if !indices.is_empty() {
+            tree.build(particles, indices, min, size, 0);
+        }
+        tree
+    }
+
+    /// Recursively builds the cell covering `indices` and returns its index in `nodes`.
+    fn build(
+        &mut self,
+        particles: &[Particle],
+        indices: Vec<usize>,
+        min: Vector2<f32>,
+        size: f32,
+        depth: usize,
+    ) -> usize {
+        let mut charge = 0.0;
+        let mut weight = 0.0;
+        let mut centre = Vector2 { x: 0.0, y: 0.0 };
+        for &i in &indices {
+            let q = particles[i].charge;
+            charge += q;
+            weight += q.abs();
+            centre.x += q.abs() * particles[i].position.x;
+            centre.y += q.abs() * particles[i].position.y;
+        }
+        if weight > 0.0 {
+            centre.x /= weight;
+            centre.y /= weight;
+        } else {
+            centre = Vector2 { x: min.x + 0.5 * size, y: min.y + 0.5 * size };
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(Node { min, size, charge, centre, children: None, particles: Vec::new() });
+
+        if indices.len() <= 1 || depth >= MAX_DEPTH {
+            self.nodes[index].particles = indices;
+            return index;
+        }
+
+        // Partition into quadrants: 0 = low x/low y, 1 = high x/low y, 2 = low x/high y, 3 = high x/high y.
+        let half = 0.5 * size;
+        let mut quadrants: [Vec<usize>; 4] = Default::default();
+        for i in indices {
+            let position = particles[i].position;
+            let east = (position.x >= min.x + half) as usize;
+            let south = (position.y >= min.y + half) as usize;
+            quadrants[east + 2 * south].push(i);
+        }
+
+        let mut children = [0; 4];
+        for (quadrant, members) in quadrants.into_iter().enumerate() {
+            let child_min = Vector2 {
+                x: min.x + half * (quadrant % 2) as f32,
+                y: min.y + half * (quadrant / 2) as f32,
+            };
+            children[quadrant] = self.build(particles, members, child_min, half, depth + 1);
+        }
+        self.nodes[index].children = Some(children);
+        index
+    }
+
+    /// Computes the force on particle `target` by walking the tree with opening angle `theta`.
+    fn force_on(&self, particles: &[Particle], law: &CoulombLaw, target: usize, theta: f32) -> Vector2<f32> {
+        let mut force = Vector2 { x: 0.0, y: 0.0 };
+        if self.nodes.is_empty() {
+            return force;
+        }
+
+        let particle = &particles[target];
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            match node.children {
+                Some(children) => {
+                    let dx = node.centre.x - particle.position.x;
+                    let dy = node.centre.y - particle.position.y;
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    // Far enough away (and not containing the target): treat the cell as one charge.
+                    if node.size < theta * distance && !node.contains(particle.position) {
+                        let f = law.force_on(particle, node.charge, node.centre);
+                        force.x += f.x;
+                        force.y += f.y;
+                    } else {
+                        stack.extend_from_slice(&children);
+                    }
+                }
+                None => {
+                    for &other in node.particles.iter().filter(|&&other| other != target) {
+                        let source = &particles[other];
+                        let f = law.force_on(particle, source.charge, source.position);
+                        force.x += f.x;
+                        force.y += f.y;
+                    }
+                }
+            }
+        }
+        force
+    }
+}
+
+/// Computes the exact Coulomb force on every particle by summing over all other particles.
+fn direct_forces(particles: &[Particle], law: &CoulombLaw) -> Vec<Vector2<f32>> {
+    particles
+        .iter()
+        .enumerate()
+        .map(|(i, particle)| {
+            let mut force = Vector2 { x: 0.0, y: 0.0 };
+            for (j, source) in particles.iter().enumerate() {
+                if i != j {
+                    let f = law.force_on(particle, source.charge, source.position);
+                    force.x += f.x;
+                    force.y += f.y;
+                }
+            }
+            force
+        })
+        .collect()
+}
+
+/// Barnes–Hut evaluator for `CoulombLaw`, replacing the O(N²) pair loop with an O(N log N) tree walk.
+///
+/// A cell of side `s` at distance `d` from a particle is approximated by its net charge when
+/// `s / d < theta`. `theta = 0` reproduces the direct sum; values around 0.5 are a common compromise.
+pub struct BarnesHut {
+    /// Opening angle.
+    pub theta: f32,
+    /// When set, every evaluation also computes the direct sum and records the relative error.
+    pub measure_error: bool,
+    /// Relative force error of the last evaluation, if `measure_error` was set.
+    pub last_error: Option<f32>,
+}
+
+impl BarnesHut {
+    pub fn new(theta: f32) -> BarnesHut {
+        BarnesHut { theta, measure_error: false, last_error: None }
+    }
+
+    /// Computes the Coulomb force on every particle from the tree.
+    ///
+    /// With `theta = 0` every cell would be opened, so the direct sum is returned as it is.
+    pub fn forces(&self, particles: &[Particle], law: &CoulombLaw) -> Vec<Vector2<f32>> {
+        if self.theta <= 0.0 {
+            return direct_forces(particles, law);
+        }
+        let tree = QuadTree::new(particles);
+        (0..particles.len())
+            .map(|i| tree.force_on(particles, law, i, self.theta))
+            .collect()
+    }
+
+    /// Adds the Coulomb force to each particle's `force`.
+    ///
+    /// When `measure_error` is set, the direct sum is evaluated as well and `last_error` records
+    /// `sqrt(Σ|F_tree - F_direct|² / Σ|F_direct|²)`, which is what `theta` should be tuned against.
+    pub fn accumulate_forces(&mut self, particles: &mut [Particle], law: &CoulombLaw) {
+        let forces = self.forces(particles, law);
+        self.last_error = if self.measure_error {
+            Some(relative_error(&forces, &direct_forces(particles, law)))
+        } else {
+            None
+        };
+        for (particle, force) in particles.iter_mut().zip(forces) {
+            particle.force.x += force.x;
+            particle.force.y += force.y;
+        }
+    }
+}
+
+fn relative_error(approximate: &[Vector2<f32>], exact: &[Vector2<f32>]) -> f32 {
+    let mut error_sq = 0.0;
+    let mut norm_sq = 0.0;
+    for (a, e) in approximate.iter().zip(exact) {
+        error_sq += (a.x - e.x).powi(2) + (a.y - e.y).powi(2);
+        norm_sq += e.x.powi(2) + e.y.powi(2);
+    }
+    if norm_sq > 0.0 { (error_sq / norm_sq).sqrt() } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use crate::physics::container::{ParticleContainer, TEST_BOUNDARIES};
+    use crate::physics::laws::build_coulomb_law;
+    use crate::physics::utils::SimRng;
+    use super::*;
+
+    /// Particles scattered over a 1000 x 700 box, all carrying a positive charge.
+    fn like_charges(num: usize) -> Vec<Particle> {
+        let mut rng = SimRng::seed_from_u64(5);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        (0..num)
+            .map(|_| {
+                let mut particle = Particle::new(&container, &mut rng);
+                particle.charge = rng.gen_range(0.0005..0.0015);
+                particle
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_theta_reproduces_the_direct_sum() {
+        let particles = like_charges(200);
+        let law = build_coulomb_law();
+        let bits = |forces: Vec<Vector2<f32>>| forces.iter().map(|f| [f.x.to_bits(), f.y.to_bits()]).collect::<Vec<_>>();
+        assert_eq!(bits(BarnesHut::new(0.0).forces(&particles, &law)), bits(direct_forces(&particles, &law)));
+        // A tree opening all but the most distant cells agrees up to rounding.
+        let forces = BarnesHut::new(1e-3).forces(&particles, &law);
+        assert!(relative_error(&forces, &direct_forces(&particles, &law)) < 1e-5);
+    }
+
+    #[test]
+    fn reported_error_is_small_at_the_default_theta() {
+        let mut particles = like_charges(500);
+        let mut tree = BarnesHut::new(0.5);
+        tree.measure_error = true;
+        tree.accumulate_forces(&mut particles, &build_coulomb_law());
+        let error = tree.last_error.unwrap();
+        assert!(error > 0.0 && error < 1e-2, "{}", error);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::BTreeSet;
+use mint::Vector2;
+use crate::physics::container::ParticleContainer;
+use crate::physics::laws::InteractionLaw;
+use crate::physics::particles::Particle;
+
+
+/// Returns mutable references to particles `i` and `j` (`i < j`) without double-borrowing.
+fn pair_mut(particles: &mut [Particle], i: usize, j: usize) -> (&mut Particle, &mut Particle) {
+    let (left, right) = particles.split_at_mut(j);
+    (&mut left[i], &mut right[0])
+}
+
+/// Resolves `law` between `particle_i` and the periodic image of `particle_j` nearest to it.
+///
+/// Along non-periodic axes the image is the particle itself, so the law sees the plain pair.
+pub fn resolve_pair(
+    particle_i: &mut Particle,
+    particle_j: &mut Particle,
+    law: &dyn InteractionLaw,
+    container: &ParticleContainer,
+) {
+    let offset = container.image_offset(particle_i.position, particle_j.position);
+    let original = particle_j.position;
+    particle_j.position.x += offset.x;
+    particle_j.position.y += offset.y;
+    let image = particle_j.position;
+    law.resolve(particle_i, particle_j);
+    // Restore the exact original position unless the law moved the particle, so pairs the law
+    // leaves alone are not perturbed by round-off.
+    if particle_j.position == image {
+        particle_j.position = original;
+    } else {
+        particle_j.position.x -= offset.x;
+        particle_j.position.y -= offset.y;
+    }
+}
+
+/// Resolves `law` for every pair `(i, j)` with `i < j`, in lexicographic order.
+///
+/// This is the O(N²) reference loop; long-range laws such as `CoulombLaw` must use it.
+pub fn resolve_all_pairs(particles: &mut [Particle], law: &dyn InteractionLaw, container: &ParticleContainer) {
+    let len = particles.len();
+    for i in 0..len {
+        for j in (i + 1)..len {
+            let (particle_i, particle_j) = pair_mut(particles, i, j);
+            resolve_pair(particle_i, particle_j, law, container);
+        }
+    }
+}
+
+/// Index of cell `cell` along an axis of `count` cells: wrapped around if `periodic`, clamped
+/// into the border cells otherwise.
+fn axis_cell(cell: f32, count: usize, periodic: bool) -> usize {
+    if periodic {
+        (cell as isize).rem_euclid(count as isize) as usize
+    } else {
+        cell.clamp(0.0, (count - 1) as f32) as usize
+    }
+}
+
+/// Cell indices next to `cell` along an axis of `count` cells, wrapping around if `periodic`.
+fn neighbour_cells(cell: usize, count: usize, periodic: bool) -> impl Iterator<Item = usize> {
+    let (cell, count) = (cell as isize, count as isize);
+    ((cell - 1)..=(cell + 1)).filter_map(move |k| {
+        if periodic {
+            Some(k.rem_euclid(count) as usize)
+        } else if (0..count).contains(&k) {
+            Some(k as usize)
+        } else {
+            None
+        }
+    })
+}
+
+/// A uniform-grid (cell-list) broad phase for short-range laws such as `ImpulseCollision`.
+///
+/// The cell size is the larger of the contact distance `2 * max(radius)` and the law's range,
+/// so two interacting particles always sit in the same or in adjacent cells. Particles outside
+/// the container are clamped into the border cells, which keeps that property intact.
+///
+/// Along periodic axes the cells tile the box exactly (and are therefore at least that large),
+/// particles outside the box are binned with their wrapped position and the first and last
+/// cells are neighbours, so pairs are also found across the boundary.
+pub struct SpatialGrid {
+    origin: Vector2<f32>,
+    cell_size: Vector2<f32>,
+    columns: usize,
+    rows: usize,
+    periodic_x: bool,
+    periodic_y: bool,
+    cells: Vec<Vec<usize>>,
+    cell_of: Vec<usize>,
+}
+
+impl SpatialGrid {
+    /// Builds a grid covering `container` for a law of the given `range` and bins every particle into it.
+    pub fn new(particles: &[Particle], container: &ParticleContainer, range: f32) -> SpatialGrid {
+        let boundaries = container.boundaries;
+        let max_radius = particles.iter().fold(0.0_f32, |acc, p| acc.max(p.radius));
+        let cell_size = (2.0 * max_radius).max(range).max(f32::EPSILON);
+        let width = (boundaries.w - boundaries.x).max(cell_size);
+        let height = (boundaries.z - boundaries.y).max(cell_size);
+        let (columns, cell_width) = if container.periodic_x {
+            let columns = (width / cell_size).floor() as usize;
+            (columns, width / columns as f32)
+        } else {
+            ((width / cell_size).ceil() as usize, cell_size)
+        };
+        let (rows, cell_height) = if container.periodic_y {
+            let rows = (height / cell_size).floor() as usize;
+            (rows, height / rows as f32)
+        } else {
+            ((height / cell_size).ceil() as usize, cell_size)
+        };
+
+        let mut grid = SpatialGrid {
+            origin: Vector2 { x: boundaries.x, y: boundaries.y },
+            cell_size: Vector2 { x: cell_width, y: cell_height },
+            columns,
+            rows,
+            periodic_x: container.periodic_x,
+            periodic_y: container.periodic_y,
+            cells: vec![Vec::new(); columns * rows],
+            cell_of: Vec::with_capacity(particles.len()),
+        };
+        for (index, particle) in particles.iter().enumerate() {
+            let cell = grid.cell_index(particle.position);
+            grid.cells[cell].push(index);
+            grid.cell_of.push(cell);
+        }
+        grid
+    }
+
+    fn cell_coords(&self, position: Vector2<f32>) -> (usize, usize) {
+        let cx = ((position.x - self.origin.x) / self.cell_size.x).floor();
+        let cy = ((position.y - self.origin.y) / self.cell_size.y).floor();
+        (axis_cell(cx, self.columns, self.periodic_x), axis_cell(cy, self.rows, self.periodic_y))
+    }
+
+    fn cell_index(&self, position: Vector2<f32>) -> usize {
+        let (cx, cy) = self.cell_coords(position);
+        cy * self.columns + cx
+    }
+
+    /// Moves particle `index` into the cell matching its new `position`.
+    fn relocate(&mut self, index: usize, position: Vector2<f32>) {
+        let new_cell = self.cell_index(position);
+        let old_cell = self.cell_of[index];
+        if new_cell != old_cell {
+            self.cells[old_cell].retain(|&other| other != index);
+            self.cells[new_cell].push(index);
+            self.cell_of[index] = new_cell;
+        }
+    }
+
+    /// Adds every particle index greater than `after` found in the 3x3 block around `position`.
+    fn collect_neighbours(&self, position: Vector2<f32>, after: usize, out: &mut BTreeSet<usize>) {
+        let (cx, cy) = self.cell_coords(position);
+        for y in neighbour_cells(cy, self.rows, self.periodic_y) {
+            for x in neighbour_cells(cx, self.columns, self.periodic_x) {
+                out.extend(self.cells[y * self.columns + x].iter().filter(|&&j| j > after));
+            }
+        }
+    }
+
+    /// Every pair `(i, j)` with `i < j` whose particles sit in the same or adjacent cells, in
+    /// lexicographic order.
+    pub fn pairs(&self, particles: &[Particle]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        let mut candidates = BTreeSet::new();
+        for (i, particle) in particles.iter().enumerate() {
+            candidates.clear();
+            self.collect_neighbours(particle.position, i, &mut candidates);
+            pairs.extend(candidates.iter().map(|&j| (i, j)));
+        }
+        pairs
+    }
+
+    /// Resolves `law` for every pair of neighbouring particles.
+    ///
+    /// Pairs are visited in the same `(i, j)` order as `resolve_all_pairs`, and the grid is kept
+    /// up to date whenever a resolution moves a particle, so the outcome is identical to the
+    /// brute-force loop for any law that leaves pairs outside its range untouched.
+    pub fn resolve_pairs(&mut self, particles: &mut [Particle], law: &dyn InteractionLaw, container: &ParticleContainer) {
+        let mut candidates = BTreeSet::new();
+        for i in 0..particles.len() {
+            candidates.clear();
+            self.collect_neighbours(particles[i].position, i, &mut candidates);
+
+            while let Some(j) = candidates.pop_first() {
+                let (particle_i, particle_j) = pair_mut(particles, i, j);
+                let before_i = particle_i.position;
+                let before_j = particle_j.position;
+                resolve_pair(particle_i, particle_j, law, container);
+
+                if particle_j.position != before_j {
+                    self.relocate(j, particle_j.position);
+                }
+                if particle_i.position != before_i {
+                    let position = particle_i.position;
+                    self.relocate(i, position);
+                    // Particle `i` moved, so its remaining partners may have changed.
+                    self.collect_neighbours(position, j, &mut candidates);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mint::Vector4;
+    use rand::SeedableRng;
+    use crate::physics::laws::{build_interaction_law, InteractionLawType};
+    use crate::physics::utils::SimRng;
+    use super::*;
+
+    /// Positions, velocities and forces of every particle, bit for bit.
+    fn state_bits(particles: &[Particle]) -> Vec<[u32; 6]> {
+        particles
+            .iter()
+            .map(|p| {
+                [p.position.x, p.position.y, p.velocity.x, p.velocity.y, p.force.x, p.force.y].map(f32::to_bits)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grid_matches_all_pairs_bit_for_bit() {
+        for law_type in [InteractionLawType::elastic_collisions(), InteractionLawType::LennardJones] {
+            let law = build_interaction_law(law_type);
+            for periodic in [false, true] {
+                for seed in 0..20 {
+                    let mut container = ParticleContainer::new(Vector4 { x: 0.0, y: 0.0, z: 200.0, w: 300.0 });
+                    container.periodic_x = periodic;
+                    container.periodic_y = periodic;
+                    let mut rng = SimRng::seed_from_u64(seed);
+                    let particles: Vec<Particle> = (0..300).map(|_| Particle::new(&container, &mut rng)).collect();
+
+                    let mut brute_force = particles.clone();
+                    resolve_all_pairs(&mut brute_force, law.as_ref(), &container);
+                    let mut grid_particles = particles;
+                    let mut grid = SpatialGrid::new(&grid_particles, &container, law.range().unwrap());
+                    grid.resolve_pairs(&mut grid_particles, law.as_ref(), &container);
+
+                    assert_eq!(
+                        state_bits(&brute_force),
+                        state_bits(&grid_particles),
+                        "{:?}, periodic: {}, seed: {}",
+                        law_type,
+                        periodic,
+                        seed
+                    );
+                }
+            }
+        }
+    }
+}
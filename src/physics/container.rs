@@ -1,58 +1,359 @@
-use ggez::mint::Vector4;
+use mint::{Vector2, Vector4};
+use rand::Rng;
+use crate::physics::geometry::Shape;
+use crate::physics::partition::{Partition, PartitionSpec};
 use crate::physics::particles::Particle;
-use crate::physics::utils::mod_f32;
+use crate::physics::pressure::PressureGauge;
+use crate::physics::szilard::SzilardEngine;
+use crate::physics::utils::{mod_f32, random_gaussian, SimRng};
+
+/// The 1000 x 700 box the tests work in.
+#[cfg(test)]
+pub const TEST_BOUNDARIES: Vector4<f32> = Vector4 { x: 0.0, y: 0.0, z: 700.0, w: 1000.0 };
+
+/// The four outer walls of the container, usable as an index into `ParticleContainer::walls`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Wall {
+    Left = 0,
+    Right = 1,
+    Ceiling = 2,
+    Ground = 3,
+}
+
+impl Wall {
+    /// Unit normal of the wall, pointing into the container.
+    pub fn normal(self) -> Vector2<f32> {
+        match self {
+            Wall::Left => Vector2 { x: 1.0, y: 0.0 },
+            Wall::Right => Vector2 { x: -1.0, y: 0.0 },
+            Wall::Ceiling => Vector2 { x: 0.0, y: 1.0 },
+            Wall::Ground => Vector2 { x: 0.0, y: -1.0 },
+        }
+    }
+}
+
+/// How a wall sends particles back into the container.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WallKind {
+    /// Mirror reflection: the normal velocity component is reversed.
+    Specular,
+    /// Maxwell's model: with probability `accommodation` the particle is re-emitted diffusely
+    /// at the wall `temperature`, otherwise it is reflected specularly.
+    Diffuse { accommodation: f32, temperature: f32 },
+    /// A fully accommodating wall acting as a heat reservoir at `temperature`.
+    Thermal { temperature: f32 },
+}
 
 pub struct ParticleContainer {
     pub boundaries: Vector4<f32>,
+    /// Whether the demons are at work. While they are not, every door stands open.
     pub demon_looking: bool,
+    /// Behaviour of each outer wall, indexed by `Wall`.
+    pub walls: [WallKind; 4],
+    /// Particles leaving through the left or right wall re-enter through the opposite one.
+    pub periodic_x: bool,
+    /// Particles leaving through the ceiling or the ground re-enter through the opposite one.
+    pub periodic_y: bool,
+    /// Fixed geometry inside the box, such as slanted or curved walls and obstacles.
+    /// Particles reflect specularly off it.
+    pub shapes: Vec<Shape>,
+    /// Vertical walls splitting the box into chambers, sorted from left to right. Chamber `c`
+    /// lies between partitions `c - 1` and `c`.
+    pub partitions: Vec<Partition>,
+    /// A Szilard engine whose piston is inserted into the box and taken out again every cycle.
+    pub szilard: Option<SzilardEngine>,
+    /// Momentum delivered to the outer walls, for the pressure readings.
+    pub pressure: PressureGauge,
 }
 
 impl ParticleContainer {
     pub fn new(boundaries: Vector4<f32>) -> ParticleContainer {
-        ParticleContainer { boundaries, demon_looking: false }
+        ParticleContainer {
+            boundaries,
+            demon_looking: false,
+            walls: [WallKind::Specular; 4],
+            periodic_x: false,
+            periodic_y: false,
+            shapes: Vec::new(),
+            partitions: vec![Partition::new(PartitionSpec::middle(), boundaries)],
+            szilard: None,
+            pressure: PressureGauge::new(2.0),
+        }
+    }
+
+    /// Width and height of the container.
+    pub fn size(&self) -> Vector2<f32> {
+        Vector2 {
+            x: self.boundaries.w - self.boundaries.x,
+            y: self.boundaries.z - self.boundaries.y,
+        }
+    }
+
+    /// Whether `wall` is periodic, i.e. particles pass through it instead of bouncing.
+    pub fn is_periodic(&self, wall: Wall) -> bool {
+        match wall {
+            Wall::Left | Wall::Right => self.periodic_x,
+            Wall::Ceiling | Wall::Ground => self.periodic_y,
+        }
+    }
+
+    /// Shift to add to `to` to obtain its periodic image nearest to `from` (the minimum-image
+    /// convention). Zero along non-periodic axes.
+    pub fn image_offset(&self, from: Vector2<f32>, to: Vector2<f32>) -> Vector2<f32> {
+        let size = self.size();
+        let mut offset = Vector2 { x: 0.0, y: 0.0 };
+        if self.periodic_x {
+            offset.x = -size.x * ((to.x - from.x) / size.x).round();
+        }
+        if self.periodic_y {
+            offset.y = -size.y * ((to.y - from.y) / size.y).round();
+        }
+        offset
+    }
+
+    /// Moves `particle` through the periodic `wall` it is crossing to the opposite side of the box.
+    pub fn pass_through(&self, wall: Wall, particle: &mut Particle) {
+        let size = self.size();
+        match wall {
+            Wall::Left => particle.position.x += size.x,
+            Wall::Right => particle.position.x -= size.x,
+            Wall::Ceiling => particle.position.y += size.y,
+            Wall::Ground => particle.position.y -= size.y,
+        }
+    }
+
+    /// Brings `particle` back inside the box along the periodic axes.
+    fn wrap(&self, particle: &mut Particle) {
+        let size = self.size();
+        if self.periodic_x {
+            particle.position.x = self.boundaries.x + (particle.position.x - self.boundaries.x).rem_euclid(size.x);
+        }
+        if self.periodic_y {
+            particle.position.y = self.boundaries.y + (particle.position.y - self.boundaries.y).rem_euclid(size.y);
+        }
     }
 
-    pub fn collision(&mut self, particle: &mut Particle) {
-        let middle: f32 = (self.boundaries.w - self.boundaries.x) / 2.0;
+    /// Sends `particle` back off `wall` according to the wall's kind, booking the momentum it
+    /// delivers to the wall with the pressure gauge.
+    pub fn bounce(&mut self, wall: Wall, particle: &mut Particle, rng: &mut SimRng) {
+        let normal = wall.normal();
+        let normal_velocity = particle.velocity.x * normal.x + particle.velocity.y * normal.y;
+        let temperature = match self.walls[wall as usize] {
+            WallKind::Specular => None,
+            WallKind::Diffuse { accommodation, temperature } => {
+                if rng.gen_range(0.0..1.0) < accommodation { Some(temperature) } else { None }
+            }
+            WallKind::Thermal { temperature } => Some(temperature),
+        };
+
+        match temperature {
+            None => {
+                if normal.x != 0.0 {
+                    particle.velocity.x *= -1.0;
+                } else {
+                    particle.velocity.y *= -1.0;
+                }
+            }
+            Some(temperature) => {
+                // Re-emit with the flux-weighted wall distribution: a Rayleigh-distributed normal
+                // component and a Gaussian tangential component, both with variance kT / m.
+                let sigma = (temperature * particle.inverse_mass()).sqrt();
+                let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+                let normal_speed = sigma * (-2.0 * u.ln()).sqrt();
+                let tangential_speed = sigma * random_gaussian(rng);
+                particle.velocity.x = normal_speed * normal.x - tangential_speed * normal.y;
+                particle.velocity.y = normal_speed * normal.y + tangential_speed * normal.x;
+            }
+        }
+
+        if particle.inverse_mass() > 0.0 {
+            let change = particle.velocity.x * normal.x + particle.velocity.y * normal.y - normal_velocity;
+            let chamber = self.chamber_of(particle);
+            self.pressure.record(wall, chamber, particle.mass * change);
+        }
+    }
+
+    /// Keeps particle `index` inside the container: bounces it off (or wraps it through) the
+    /// walls, the fixed geometry and the partitions.
+    pub fn collision(&mut self, index: usize, particle: &mut Particle, rng: &mut SimRng) {
+        // Periodic axes wrap around instead of bouncing.
+        self.wrap(particle);
 
         // Collision with left-most wall.
-        if particle.radius >= mod_f32(self.boundaries.x - particle.position.x) {
+        if !self.periodic_x && particle.radius >= mod_f32(self.boundaries.x - particle.position.x) {
             particle.position.x = particle.radius;
-            particle.velocity.x *= -1.0;
+            self.bounce(Wall::Left, particle, rng);
         }
 
         // Collision with right-most wall.
-        if particle.radius >= mod_f32(self.boundaries.w - particle.position.x) {
+        if !self.periodic_x && particle.radius >= mod_f32(self.boundaries.w - particle.position.x) {
             particle.position.x = self.boundaries.w - particle.radius;
-            particle.velocity.x *= -1.0;
+            self.bounce(Wall::Right, particle, rng);
         }
 
         // Collision with ceiling.
-        if particle.radius >= mod_f32(self.boundaries.y - particle.position.y) {
+        if !self.periodic_y && particle.radius >= mod_f32(self.boundaries.y - particle.position.y) {
             particle.position.y = particle.radius;
-            particle.velocity.y *= -1.0;
+            self.bounce(Wall::Ceiling, particle, rng);
         }
 
         // Collision with ground.
-        if particle.radius >= mod_f32(self.boundaries.z - particle.position.y) {
+        if !self.periodic_y && particle.radius >= mod_f32(self.boundaries.z - particle.position.y) {
             particle.position.y = self.boundaries.z - particle.radius;
-            particle.velocity.y *= -1.0;
+            self.bounce(Wall::Ground, particle, rng);
         }
 
-        // Collision with middle wall.
-        if particle.radius >= mod_f32(middle - particle.position.x) && self.demon_looking {
-            let sq: f32 = particle.velocity.x.powf(2.0) + particle.velocity.y.powf(2.0);
-            let speed = sq.sqrt();
+        // Collision with the fixed geometry.
+        for shape in &self.shapes {
+            shape.collide(particle);
+        }
 
-            // ->  only if speed >= 3.0
-            if particle.velocity.x <= 0.0 && speed < 140.0 {
-                particle.position.x += particle.radius;
-                particle.velocity.x *= -1.0;
+        // Collision with the partition walls.
+        let looking = self.demon_looking;
+        let boundaries = self.boundaries;
+        for partition in &mut self.partitions {
+            partition.collide(index, particle, looking, boundaries, rng);
+        }
+
+        // Collision with the Szilard engine's piston.
+        if let Some(engine) = self.szilard.as_mut() {
+            engine.collide(particle);
+        }
+    }
+
+    /// Decides whether partition `partition` sends `particle` back when it reaches it.
+    pub fn partition_reflects(&mut self, partition: usize, particle: &Particle, rng: &mut SimRng) -> bool {
+        let looking = self.demon_looking;
+        let boundaries = self.boundaries;
+        self.partitions[partition].reflects(particle, looking, boundaries, rng)
+    }
+
+    /// Number of chambers the partitions split the box into.
+    pub fn chamber_count(&self) -> usize {
+        self.partitions.len() + 1
+    }
+
+    /// Left and right x coordinates of chamber `chamber`.
+    pub fn chamber_bounds(&self, chamber: usize) -> (f32, f32) {
+        let left = if chamber == 0 { self.boundaries.x } else { self.partitions[chamber - 1].x };
+        let right = self.partitions.get(chamber).map_or(self.boundaries.w, |partition| partition.x);
+        (left, right)
+    }
+
+    /// Area of chamber `chamber` of the box, not counting any fixed geometry inside it.
+    pub fn chamber_area(&self, chamber: usize) -> f32 {
+        let (left, right) = self.chamber_bounds(chamber);
+        (right - left) * (self.boundaries.z - self.boundaries.y)
+    }
+
+    /// Length of `wall`; periodic walls have none, since particles cannot push on them.
+    pub fn wall_length(&self, wall: Wall) -> f32 {
+        let size = self.size();
+        match wall {
+            _ if self.is_periodic(wall) => 0.0,
+            Wall::Left | Wall::Right => size.y,
+            Wall::Ceiling | Wall::Ground => size.x,
+        }
+    }
+
+    /// Total length of the outer walls around chamber `chamber`: its stretch of the ceiling and
+    /// the ground, and the left or right wall at either end of the box. Periodic walls do not
+    /// count, and neither do the partitions.
+    pub fn chamber_wall_length(&self, chamber: usize) -> f32 {
+        let (left, right) = self.chamber_bounds(chamber);
+        let mut length = 0.0;
+        if !self.periodic_y {
+            length += 2.0 * (right - left);
+        }
+        if chamber == 0 {
+            length += self.wall_length(Wall::Left);
+        }
+        if chamber + 1 == self.chamber_count() {
+            length += self.wall_length(Wall::Right);
+        }
+        length
+    }
+
+    /// Pressure on `wall`, i.e. force per unit length in 2D: the impulse it received over the
+    /// gauge's window divided by the window and by its length.
+    pub fn wall_pressure(&self, wall: Wall) -> f32 {
+        let exposure = self.pressure.duration() * self.wall_length(wall);
+        if exposure > 0.0 { self.pressure.wall_impulse(wall) / exposure } else { 0.0 }
+    }
+
+    /// Pressure of the gas in chamber `chamber`, measured on the outer walls around it.
+    ///
+    /// The partitions are left out, so the reading does not depend on how a door is working.
+    pub fn chamber_pressure(&self, chamber: usize) -> f32 {
+        let exposure = self.pressure.duration() * self.chamber_wall_length(chamber);
+        if exposure > 0.0 { self.pressure.chamber_impulse(chamber) / exposure } else { 0.0 }
+    }
+
+    /// Index of the chamber `particle` is in, counting from 0 on the left.
+    pub fn chamber_of(&self, particle: &Particle) -> usize {
+        self.partitions.iter().take_while(|partition| partition.x <= particle.position.x).count()
+    }
+
+    /// Runs the Szilard engine's cycle for a step of length `dt`, if there is one.
+    pub fn advance_szilard(&mut self, particles: &[Particle], dt: f32) {
+        let looking = self.demon_looking;
+        let boundaries = self.boundaries;
+        if let Some(engine) = self.szilard.as_mut() {
+            engine.advance(particles, looking, boundaries, dt);
+        }
+    }
+
+    /// Advances every demon's clock by one step of length `dt`.
+    pub fn tick_demons(&mut self, dt: f32) {
+        for partition in &mut self.partitions {
+            partition.tick(dt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::physics::scenario::Scenario;
+    use crate::physics::world::World;
+    use super::*;
+
+    #[test]
+    fn a_gas_between_thermal_walls_takes_the_wall_temperature() {
+        // Without collisions between them, every particle is heated by the walls alone.
+        let scenario = Scenario {
+            walls: [WallKind::Thermal { temperature: 20000.0 }; 4],
+            laws: Vec::new(),
+            ..Scenario::ideal_gas(300)
+        };
+        let mut world = World::new(600.0, 400.0, scenario);
+        assert!((world.average_kinetic_energy() / 20000.0 - 1.0).abs() > 0.2);
+        let mut total = 0.0;
+        for step in 0..1800 {
+            world.step(1.0 / 60.0);
+            // Average over the last twenty seconds, well after the gas has relaxed, since the
+            // temperature of a few hundred particles fluctuates by several percent.
+            if step >= 600 {
+                total += world.average_kinetic_energy();
             }
-            // <- only if speed >= 80.0
-            else if particle.velocity.x >= 0.0 && speed > 10.0 {
-                particle.position.x -= particle.radius;
-                particle.velocity.x *= -1.0;
+        }
+        let kt = total / 1200.0;
+        assert!((kt / 20000.0 - 1.0).abs() < 0.05, "{}", kt);
+    }
+
+    #[test]
+    fn a_diffuse_wall_that_never_accommodates_is_specular() {
+        let mut diffuse = ParticleContainer::new(TEST_BOUNDARIES);
+        diffuse.walls = [WallKind::Diffuse { accommodation: 0.0, temperature: 5000.0 }; 4];
+        let mut specular = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut rng = SimRng::seed_from_u64(4);
+        for wall in [Wall::Left, Wall::Right, Wall::Ceiling, Wall::Ground] {
+            for _ in 0..100 {
+                let mut particle = Particle::new(&diffuse, &mut rng);
+                let mut mirrored = particle.clone();
+                diffuse.bounce(wall, &mut particle, &mut rng);
+                specular.bounce(wall, &mut mirrored, &mut rng);
+                assert_eq!((particle.velocity.x, particle.velocity.y), (mirrored.velocity.x, mirrored.velocity.y));
             }
         }
     }
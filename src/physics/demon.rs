@@ -0,0 +1,423 @@
+use std::collections::VecDeque;
+use rand::Rng;
+use crate::physics::particles::Particle;
+use crate::physics::utils::random_gaussian;
+
+
+/// Which way a particle crosses a partition wall, usable as an index into `DemonLedger` totals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight = 0,
+    RightToLeft = 1,
+}
+
+impl Direction {
+    /// The direction `particle` is heading in, from the sign of its horizontal velocity.
+    pub fn of(particle: &Particle) -> Direction {
+        if particle.velocity.x > 0.0 { Direction::LeftToRight } else { Direction::RightToLeft }
+    }
+}
+
+/// What the demon does with a particle arriving at its door.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Pass,
+    Reflect,
+}
+
+/// How faithfully the demon sees the particles it sorts.
+///
+/// The measured speed is the true speed plus Gaussian noise of standard deviation `speed_noise`,
+/// reported as the centre of its bin of width `resolution` (`0` for no binning). Independently,
+/// each decision is turned into its opposite with probability `misclassification`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MeasurementModel {
+    pub speed_noise: f32,
+    pub misclassification: f32,
+    pub resolution: f32,
+}
+
+impl MeasurementModel {
+    /// A perfect measurement.
+    pub fn exact() -> MeasurementModel {
+        MeasurementModel::default()
+    }
+
+    /// What the demon sees of `particle`: a copy moving in the same direction at the measured speed.
+    pub fn measure<R: Rng>(&self, particle: &Particle, rng: &mut R) -> Particle {
+        let speed = particle.speed();
+        let mut measured_speed = speed;
+        if self.speed_noise > 0.0 {
+            measured_speed = (measured_speed + self.speed_noise * random_gaussian(rng)).max(0.0);
+        }
+        if self.resolution > 0.0 {
+            measured_speed = ((measured_speed / self.resolution).floor() + 0.5) * self.resolution;
+        }
+
+        let mut measured = particle.clone();
+        if speed > 0.0 {
+            measured.velocity.x *= measured_speed / speed;
+            measured.velocity.y *= measured_speed / speed;
+        }
+        measured
+    }
+
+    /// Applies the misclassification error to a decision taken on a measured particle.
+    pub fn corrupt<R: Rng>(&self, decision: Decision, rng: &mut R) -> Decision {
+        if self.misclassification > 0.0 && rng.gen_range(0.0..1.0) < self.misclassification {
+            match decision {
+                Decision::Pass => Decision::Reflect,
+                Decision::Reflect => Decision::Pass,
+            }
+        } else {
+            decision
+        }
+    }
+}
+
+/// How much work the demon can do.
+///
+/// A decision reaches the door `latency` ticks of the demon's clock after the measurement it is
+/// based on; until then, arriving particles meet the door as earlier decisions left it. The
+/// clock ticks once per call to `World::step`, i.e. once per frame on screen, not once per
+/// sub-step, so the delay in seconds follows the frame time unless a fixed time step is used.
+/// At most `max_rate` particles per unit time can be measured (`None` for no limit), and
+/// particles arriving while the demon has no capacity left get `busy_outcome` without being
+/// measured.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DemonLimits {
+    pub latency: u32,
+    pub max_rate: Option<f32>,
+    pub busy_outcome: Decision,
+}
+
+impl DemonLimits {
+    /// An instant demon with unlimited capacity.
+    pub fn unlimited() -> DemonLimits {
+        DemonLimits { latency: 0, max_rate: None, busy_outcome: Decision::Pass }
+    }
+}
+
+/// Run-time state of a demon working within `DemonLimits`.
+#[derive(Clone, Debug)]
+pub struct DemonSchedule {
+    /// Number of ticks so far.
+    step: u64,
+    /// Decisions on their way to the door, with the tick at which they reach it.
+    pending: VecDeque<(u64, Decision)>,
+    /// Decision the door is currently acting on.
+    door: Decision,
+    /// Measurements the demon can still make before it is busy.
+    budget: f32,
+}
+
+impl Default for DemonSchedule {
+    fn default() -> DemonSchedule {
+        DemonSchedule { step: 0, pending: VecDeque::new(), door: Decision::Pass, budget: 1.0 }
+    }
+}
+
+impl DemonSchedule {
+    /// Advances by one tick of length `dt`: refills the measurement budget and lets every
+    /// decision that is due reach the door.
+    pub fn tick(&mut self, limits: &DemonLimits, dt: f32) {
+        self.step += 1;
+        if let Some(rate) = limits.max_rate {
+            let capacity = (rate * dt).max(1.0);
+            self.budget = (self.budget + rate * dt).min(capacity);
+        }
+        while let Some(&(due, decision)) = self.pending.front() {
+            if due > self.step {
+                break;
+            }
+            self.door = decision;
+            self.pending.pop_front();
+        }
+    }
+
+    /// Whether the demon has capacity for another measurement, using it up if so.
+    pub fn try_measure(&mut self, limits: &DemonLimits) -> bool {
+        if limits.max_rate.is_none() {
+            return true;
+        }
+        if self.budget >= 1.0 {
+            self.budget -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sends `decision` on its way to the door and returns what the door does now.
+    pub fn submit(&mut self, limits: &DemonLimits, decision: Decision) -> Decision {
+        if limits.latency == 0 {
+            self.door = decision;
+        } else {
+            self.pending.push_back((self.step + limits.latency as u64, decision));
+        }
+        self.door
+    }
+}
+
+/// Running totals of what crossed one partition wall and of what its demon's sorting cost.
+///
+/// Every measurement yields one bit that must eventually be erased, and by Landauer's principle
+/// erasing it dissipates at least `kT ln 2` of heat, i.e. produces an entropy of `ln 2` (`k = 1`).
+/// The second law holds as long as the entropy change of the gas plus `erasure_entropy` is not
+/// negative.
+#[derive(Clone, Debug, Default)]
+pub struct DemonLedger {
+    /// Number of particles that crossed, indexed by `Direction`.
+    pub transfers: [u32; 2],
+    /// Kinetic energy carried across, indexed by `Direction`.
+    pub energy: [f32; 2],
+    /// Number of particles the demon has measured.
+    pub measurements: u32,
+    /// Landauer cost of erasing the measured bits, charged at the gas temperature when settled.
+    pub landauer_cost: f32,
+    /// Measurements already included in `landauer_cost`.
+    settled: u32,
+}
+
+impl DemonLedger {
+    pub fn record_transfer(&mut self, direction: Direction, kinetic_energy: f32) {
+        self.transfers[direction as usize] += 1;
+        self.energy[direction as usize] += kinetic_energy;
+    }
+
+    pub fn record_measurement(&mut self) {
+        self.measurements += 1;
+    }
+
+    /// Charges `kT ln 2` for every measurement made since the last call.
+    pub fn settle(&mut self, kt: f32) {
+        let new_bits = self.measurements - self.settled;
+        self.landauer_cost += new_bits as f32 * kt * std::f32::consts::LN_2;
+        self.settled = self.measurements;
+    }
+
+    /// Net kinetic energy moved into the chamber on the left of the wall.
+    pub fn heat_to_left(&self) -> f32 {
+        self.energy[Direction::RightToLeft as usize] - self.energy[Direction::LeftToRight as usize]
+    }
+
+    /// Minimum entropy produced by erasing the demon's memory, `ln 2` per measured bit.
+    pub fn erasure_entropy(&self) -> f32 {
+        self.measurements as f32 * std::f32::consts::LN_2
+    }
+}
+
+/// The demon's sorting rule: decides, from the state of a particle arriving at the door and
+/// the direction it is heading in, whether to let it through.
+pub trait DemonPolicy {
+    fn decide(&mut self, particle: &Particle, direction: Direction) -> Decision;
+
+    /// Called after every step with the whole gas, so adaptive policies can track it.
+    fn observe(&mut self, _particles: &[Particle], _dt: f32) {}
+}
+
+/// Sorts particles into a hot left chamber and a cold right chamber: only particles faster than
+/// `fast_above` may enter the left chamber, and only particles slower than `slow_below` may
+/// enter the right one.
+pub struct SpeedThreshold {
+    pub fast_above: f32,
+    pub slow_below: f32,
+}
+
+impl DemonPolicy for SpeedThreshold {
+    fn decide(&mut self, particle: &Particle, direction: Direction) -> Decision {
+        let passes = match direction {
+            Direction::RightToLeft => particle.speed() >= self.fast_above,
+            Direction::LeftToRight => particle.speed() <= self.slow_below,
+        };
+        if passes { Decision::Pass } else { Decision::Reflect }
+    }
+}
+
+/// Sorts at the median speed of the gas, so that about half of the arrivals count as fast
+/// however hot the gas gets. Fast particles are let into the left chamber, slow ones into the
+/// right chamber.
+///
+/// The median follows from a running average of the kinetic energy per particle, smoothed over
+/// the time constant `tau`: in 2D the speeds are Rayleigh distributed with `σ² = kT / m`, whose
+/// median is `σ sqrt(2 ln 2)`.
+pub struct MedianSpeed {
+    pub tau: f32,
+    /// Running average of the kinetic energy per particle, i.e. `kT`.
+    pub average_kinetic_energy: Option<f32>,
+}
+
+impl MedianSpeed {
+    /// Median speed of a particle of the given `mass` at the current average energy.
+    pub fn median_speed(&self, mass: f32) -> f32 {
+        let kt = self.average_kinetic_energy.unwrap_or(0.0);
+        (2.0 * std::f32::consts::LN_2 * kt / mass).sqrt()
+    }
+}
+
+impl DemonPolicy for MedianSpeed {
+    fn decide(&mut self, particle: &Particle, direction: Direction) -> Decision {
+        let fast = particle.speed() >= self.median_speed(particle.mass);
+        match (direction, fast) {
+            (Direction::RightToLeft, true) | (Direction::LeftToRight, false) => Decision::Pass,
+            _ => Decision::Reflect,
+        }
+    }
+
+    fn observe(&mut self, particles: &[Particle], dt: f32) {
+        if particles.is_empty() {
+            return;
+        }
+        let current = particles.iter().map(|particle| particle.kinetic_energy()).sum::<f32>() / particles.len() as f32;
+        self.average_kinetic_energy = Some(match self.average_kinetic_energy {
+            Some(average) => average + (dt / self.tau).min(1.0) * (current - average),
+            None => current,
+        });
+    }
+}
+
+/// Lets every particle through: the door behaves as an open hole.
+pub struct PassAll;
+
+impl DemonPolicy for PassAll {
+    fn decide(&mut self, _particle: &Particle, _direction: Direction) -> Decision {
+        Decision::Pass
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DemonPolicyType {
+    SpeedThreshold { fast_above: f32, slow_below: f32 },
+    MedianSpeed { tau: f32 },
+    PassAll,
+}
+
+impl DemonPolicyType {
+    /// Short name for display.
+    pub fn name(self) -> &'static str {
+        match self {
+            DemonPolicyType::SpeedThreshold { .. } => "speed threshold",
+            DemonPolicyType::MedianSpeed { .. } => "median speed",
+            DemonPolicyType::PassAll => "pass all",
+        }
+    }
+
+    /// The next policy in the cycle, with default parameters, used by the renderer to switch
+    /// policies at runtime.
+    pub fn next(self) -> DemonPolicyType {
+        match self {
+            DemonPolicyType::SpeedThreshold { .. } => DemonPolicyType::MedianSpeed { tau: 1.0 },
+            DemonPolicyType::MedianSpeed { .. } => DemonPolicyType::PassAll,
+            DemonPolicyType::PassAll => DemonPolicyType::SpeedThreshold { fast_above: 140.0, slow_below: 10.0 },
+        }
+    }
+}
+
+/// Creates and returns a demon policy based on the given `DemonPolicyType`.
+pub fn build_demon_policy(policy_type: DemonPolicyType) -> Box<dyn DemonPolicy> {
+    match policy_type {
+        DemonPolicyType::SpeedThreshold { fast_above, slow_below } => Box::new(SpeedThreshold { fast_above, slow_below }),
+        DemonPolicyType::MedianSpeed { tau } => Box::new(MedianSpeed { tau, average_kinetic_energy: None }),
+        DemonPolicyType::PassAll => Box::new(PassAll),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::physics::container::{ParticleContainer, TEST_BOUNDARIES};
+    use crate::physics::initial_conditions::{assign_velocities, VelocityDistribution};
+    use crate::physics::utils::SimRng;
+    use super::*;
+
+    /// A gas of `num` particles with Maxwell–Boltzmann velocities at `temperature`.
+    fn maxwell_boltzmann_gas(num: usize, temperature: f32) -> Vec<Particle> {
+        let mut rng = SimRng::seed_from_u64(2);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut particles: Vec<Particle> = (0..num).map(|_| Particle::new(&container, &mut rng)).collect();
+        let distribution = VelocityDistribution::MaxwellBoltzmann { temperature, remove_drift: true, exact_energy: true };
+        assign_velocities(&mut particles, distribution, &mut rng);
+        particles
+    }
+
+    #[test]
+    fn each_measured_bit_is_charged_once_at_the_temperature_it_is_settled_at() {
+        let mut ledger = DemonLedger::default();
+        for _ in 0..3 {
+            ledger.record_measurement();
+        }
+        ledger.settle(2.0);
+        assert_eq!(ledger.landauer_cost, 3.0 * 2.0 * std::f32::consts::LN_2);
+        // Nothing new to charge, whatever the temperature is now.
+        ledger.settle(5.0);
+        assert_eq!(ledger.landauer_cost, 3.0 * 2.0 * std::f32::consts::LN_2);
+        ledger.record_measurement();
+        ledger.settle(5.0);
+        assert!((ledger.landauer_cost - 11.0 * std::f32::consts::LN_2).abs() < 1e-5);
+        assert!((ledger.erasure_entropy() - 4.0 * std::f32::consts::LN_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn speed_threshold_reproduces_the_original_door_rule() {
+        // The rule the middle wall used before demons had policies: heading left, particles
+        // slower than 140 are turned back; heading right, particles faster than 10 are.
+        let original_reflects = |vx: f32, speed: f32| (vx <= 0.0 && speed < 140.0) || (vx >= 0.0 && speed > 10.0);
+        let mut policy = build_demon_policy(DemonPolicyType::SpeedThreshold { fast_above: 140.0, slow_below: 10.0 });
+        for speed in [0.0_f32, 5.0, 10.0, 10.5, 80.0, 139.5, 140.0, 141.0, 400.0] {
+            for vx in [-speed, -0.6 * speed, 0.6 * speed, speed] {
+                let vy = (speed * speed - vx * vx).max(0.0).sqrt();
+                let particle = Particle::at(500.0, 350.0, vx, vy);
+                let decision = policy.decide(&particle, Direction::of(&particle));
+                assert_eq!(decision == Decision::Reflect, original_reflects(vx, particle.speed()), "v = ({}, {})", vx, vy);
+            }
+        }
+        assert_eq!(PassAll.decide(&Particle::at(500.0, 350.0, -1.0, 0.0), Direction::RightToLeft), Decision::Pass);
+    }
+
+    #[test]
+    fn median_speed_tracks_the_average_energy_and_splits_the_gas_in_half() {
+        let gas = maxwell_boltzmann_gas(20000, 10000.0);
+        let mut policy = MedianSpeed { tau: 1.0, average_kinetic_energy: None };
+        policy.observe(&gas, 0.1);
+        assert!((policy.average_kinetic_energy.unwrap() / 10000.0 - 1.0).abs() < 1e-3);
+
+        let median = policy.median_speed(1.0);
+        let fast = gas.iter().filter(|particle| particle.speed() >= median).count() as f32 / gas.len() as f32;
+        assert!((fast - 0.5).abs() < 0.01, "{}", fast);
+        let admitted = gas.iter().filter(|particle| policy.decide(particle, Direction::RightToLeft) == Decision::Pass).count();
+        assert!((admitted as f32 / gas.len() as f32 - 0.5).abs() < 0.01);
+
+        // After the gas is heated, the running average relaxes to the new energy over `tau`.
+        let hot = maxwell_boltzmann_gas(20000, 30000.0);
+        let mut previous = 10000.0;
+        for _ in 0..100 {
+            policy.observe(&hot, 0.1);
+            let average = policy.average_kinetic_energy.unwrap();
+            assert!(average > previous && average <= 30000.0 * 1.001);
+            previous = average;
+        }
+        assert!((previous / 30000.0 - 1.0).abs() < 1e-3, "{}", previous);
+    }
+
+    #[test]
+    fn measurement_errors_match_the_configured_noise() {
+        let model = MeasurementModel { speed_noise: 20.0, misclassification: 0.1, resolution: 0.0 };
+        let particle = Particle::at(0.0, 0.0, 180.0, -240.0);
+        let mut rng = SimRng::seed_from_u64(11);
+        let num = 20000;
+
+        let errors: Vec<f32> = (0..num).map(|_| model.measure(&particle, &mut rng).speed() - 300.0).collect();
+        let mean = errors.iter().sum::<f32>() / num as f32;
+        let std = (errors.iter().map(|e| (e - mean).powi(2)).sum::<f32>() / num as f32).sqrt();
+        assert!(mean.abs() < 0.5, "{}", mean);
+        assert!((std / model.speed_noise - 1.0).abs() < 0.03, "{}", std);
+
+        let flipped = (0..num).filter(|_| model.corrupt(Decision::Pass, &mut rng) == Decision::Reflect).count();
+        let rate = flipped as f32 / num as f32;
+        assert!((rate - model.misclassification).abs() < 0.01, "{}", rate);
+
+        let binned = MeasurementModel { resolution: 50.0, ..MeasurementModel::exact() };
+        assert_eq!(binned.measure(&particle, &mut rng).speed().round(), 325.0);
+        assert_eq!(MeasurementModel::exact().corrupt(Decision::Reflect, &mut rng), Decision::Reflect);
+    }
+}
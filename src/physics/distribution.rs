@@ -0,0 +1,94 @@
+use crate::physics::particles::Particle;
+use crate::physics::thermostats::temperature;
+
+
+/// Histogram of the kinetic energies of a group of particles, with bins measured in units of
+/// the group's own `kT`, for watching the H-theorem at work.
+///
+/// In 2D the Maxwell–Boltzmann energy distribution is the exponential `exp(-E / kT) / kT`
+/// whatever the masses, so energies rather than speeds are binned. For particles of equal mass
+/// this is the speed histogram with the speed axis squared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyHistogram {
+    /// Average kinetic energy of the particles, i.e. their `kT`.
+    pub kt: f32,
+    /// Width of every bin but the last, which collects the tail above `kt * range`.
+    pub bin_width: f32,
+    /// Fraction of the particles in each bin.
+    pub probabilities: Vec<f32>,
+}
+
+impl EnergyHistogram {
+    /// Bins the energies of the `selected` particles into `bins` bins spanning `range` times
+    /// their `kT`.
+    pub fn new(particles: &[Particle], selected: &[usize], bins: usize, range: f32) -> EnergyHistogram {
+        let kt = temperature(particles, selected);
+        let bin_width = kt * range / bins as f32;
+        let mut probabilities = vec![0.0; bins];
+        if kt > 0.0 {
+            for &i in selected {
+                let bin = ((particles[i].kinetic_energy() / bin_width) as usize).min(bins - 1);
+                probabilities[bin] += 1.0 / selected.len() as f32;
+            }
+        }
+        EnergyHistogram { kt, bin_width, probabilities }
+    }
+
+    /// Boltzmann's H for the histogram, `sum p ln p`. It only decreases as collisions relax the
+    /// gas, down to its Maxwell–Boltzmann value; `-H` is the entropy of the distribution.
+    pub fn h_function(&self) -> f32 {
+        self.probabilities.iter().filter(|&&p| p > 0.0).map(|&p| p * p.ln()).sum()
+    }
+
+    /// Probability of each bin under the Maxwell–Boltzmann distribution at the measured `kT`.
+    pub fn maxwell_boltzmann(&self) -> Vec<f32> {
+        let bins = self.probabilities.len();
+        // Fraction of the distribution above the lower edge of `bin`.
+        let tail = |bin: usize| if bin < bins { (-(bin as f32) * self.bin_width / self.kt).exp() } else { 0.0 };
+        (0..bins).map(|bin| tail(bin) - tail(bin + 1)).collect()
+    }
+
+    /// Kullback–Leibler divergence `sum p ln(p / q)` of the histogram from the Maxwell–Boltzmann
+    /// distribution `q` at the measured `kT`. Zero once the gas has relaxed, up to sampling noise.
+    pub fn kl_divergence(&self) -> f32 {
+        self.probabilities
+            .iter()
+            .zip(self.maxwell_boltzmann())
+            .filter(|&(&p, q)| p > 0.0 && q > 0.0)
+            .map(|(&p, q)| p * (p / q).ln())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::physics::container::{ParticleContainer, TEST_BOUNDARIES};
+    use crate::physics::initial_conditions::{assign_velocities, VelocityDistribution};
+    use crate::physics::utils::SimRng;
+    use super::*;
+
+    #[test]
+    fn kl_divergence_vanishes_for_a_maxwell_boltzmann_gas() {
+        let mut rng = SimRng::seed_from_u64(7);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut particles: Vec<Particle> = (0..20000).map(|_| Particle::new(&container, &mut rng)).collect();
+        let selected: Vec<usize> = (0..particles.len()).collect();
+
+        // Particles that all start at nearly the same speed are far from equilibrium.
+        let initial = EnergyHistogram::new(&particles, &selected, 20, 5.0);
+        assert!(initial.kl_divergence() > 0.1, "{}", initial.kl_divergence());
+
+        // The exact Maxwell–Boltzmann histogram has no divergence at all...
+        let exact = EnergyHistogram { probabilities: initial.maxwell_boltzmann(), ..initial.clone() };
+        assert!(exact.kl_divergence().abs() < 1e-6, "{}", exact.kl_divergence());
+        assert!(exact.h_function() < initial.h_function());
+
+        // ...and a sampled one only the sampling noise, about (bins - 1) / 2N.
+        let distribution = VelocityDistribution::MaxwellBoltzmann { temperature: 10000.0, remove_drift: true, exact_energy: true };
+        assign_velocities(&mut particles, distribution, &mut rng);
+        let sampled = EnergyHistogram::new(&particles, &selected, 20, 5.0);
+        assert!((sampled.kt / 10000.0 - 1.0).abs() < 1e-3);
+        assert!(sampled.kl_divergence() < 2e-3, "{}", sampled.kl_divergence());
+    }
+}
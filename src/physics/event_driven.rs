@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use mint::Vector2;
+use crate::physics::container::{ParticleContainer, Wall};
+use crate::physics::particles::Particle;
+use crate::physics::utils::SimRng;
+
+
+/// Distance from a partition within which a disc counts as already touching it, so a disc
+/// that has just been let through is not stopped again by round-off.
+const PARTITION_TOLERANCE: f64 = 1e-3;
+
+/// What a predicted event involves besides its first particle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Target {
+    /// Another particle.
+    Particle(usize),
+    /// One of the outer walls; for a periodic wall, the moment the centre crosses it.
+    Wall(Wall),
+    /// A partition wall with the given index, with its demon's door or guarded by its demon.
+    Partition(usize),
+}
+
+/// A predicted event, valid only while neither particle has collided since it was predicted.
+#[derive(Copy, Clone, Debug)]
+struct Event {
+    time: f64,
+    particle: usize,
+    target: Target,
+    /// Collision counts of `particle` and of the target particle (if any) at prediction time.
+    counts: (u32, u32),
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    // Reversed so that `BinaryHeap` pops the earliest event first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.time.total_cmp(&self.time)
+    }
+}
+
+/// Event-driven molecular dynamics for hard discs.
+///
+/// Instead of time-stepping, particles fly freely between collisions and the engine jumps from
+/// one predicted particle–particle, particle–wall or particle–partition event to the next, so
+/// no collision is missed and discs never tunnel. Particles are only drifted when they take
+/// part in an event or when `advance` returns, which keeps the cost per event at O(N).
+///
+/// Forces and the interaction law are ignored: particles are perfectly elastic hard discs,
+/// although thermal walls still exchange energy with them. Periodic walls are crossing events
+/// that move the disc to the opposite side, and pairs collide at their minimum-image separation.
+/// Whenever particles are changed outside the engine, call `reset` to re-predict all events.
+pub struct EventDriven {
+    /// Simulation time reached so far.
+    time: f64,
+    /// Time at which each particle's stored position was last brought up to date.
+    updated_at: Vec<f64>,
+    /// Number of events each particle has taken part in; used to discard stale predictions.
+    counts: Vec<u32>,
+    queue: BinaryHeap<Event>,
+}
+
+impl EventDriven {
+    pub fn new(particles: &[Particle], container: &ParticleContainer) -> EventDriven {
+        let mut engine = EventDriven {
+            time: 0.0,
+            updated_at: Vec::new(),
+            counts: Vec::new(),
+            queue: BinaryHeap::new(),
+        };
+        engine.reset(particles, container);
+        engine
+    }
+
+    /// Discards every prediction and predicts the next events from the current state.
+    pub fn reset(&mut self, particles: &[Particle], container: &ParticleContainer) {
+        self.updated_at = vec![self.time; particles.len()];
+        self.counts = vec![0; particles.len()];
+        self.queue.clear();
+        for i in 0..particles.len() {
+            self.predict(i, particles, container, i + 1);
+        }
+    }
+
+    /// Advances the simulation by exactly `dt`, processing every event on the way. Thermal walls
+    /// and demons draw their random numbers from `rng`.
+    pub fn advance(&mut self, particles: &mut [Particle], container: &mut ParticleContainer, rng: &mut SimRng, dt: f32) {
+        if particles.len() != self.counts.len() {
+            self.reset(particles, container);
+        }
+        let end = self.time + dt as f64;
+
+        while let Some(event) = self.queue.peek().copied() {
+            if event.time > end {
+                break;
+            }
+            self.queue.pop();
+            if !self.is_valid(&event) {
+                continue;
+            }
+            self.time = event.time;
+
+            let i = event.particle;
+            self.drift(particles, i);
+            match event.target {
+                Target::Particle(j) => {
+                    self.drift(particles, j);
+                    bounce(particles, container, i, j);
+                    self.counts[j] += 1;
+                }
+                Target::Wall(wall) if container.is_periodic(wall) => container.pass_through(wall, &mut particles[i]),
+                Target::Wall(wall) => container.bounce(wall, &mut particles[i], rng),
+                Target::Partition(partition) => {
+                    if container.partition_reflects(partition, &particles[i], rng) {
+                        particles[i].velocity.x *= -1.0;
+                    }
+                }
+            }
+            self.counts[i] += 1;
+
+            self.predict(i, particles, container, 0);
+            if let Target::Particle(j) = event.target {
+                self.predict(j, particles, container, 0);
+            }
+        }
+
+        self.time = end;
+        for i in 0..particles.len() {
+            self.drift(particles, i);
+        }
+    }
+
+    fn is_valid(&self, event: &Event) -> bool {
+        let first = self.counts[event.particle] == event.counts.0;
+        match event.target {
+            Target::Particle(j) => first && self.counts[j] == event.counts.1,
+            _ => first,
+        }
+    }
+
+    /// Moves particle `i` along its straight-line path up to the current time.
+    fn drift(&mut self, particles: &mut [Particle], i: usize) {
+        particles[i].update_position((self.time - self.updated_at[i]) as f32);
+        self.updated_at[i] = self.time;
+    }
+
+    /// Position of particle `i` at the current time, without modifying it.
+    fn position_now(&self, particles: &[Particle], i: usize) -> (f64, f64) {
+        let elapsed = self.time - self.updated_at[i];
+        let particle = &particles[i];
+        (
+            particle.position.x as f64 + particle.velocity.x as f64 * elapsed,
+            particle.position.y as f64 + particle.velocity.y as f64 * elapsed,
+        )
+    }
+
+    /// Predicts the next wall and partition events of particle `i`, and its collisions with
+    /// every other particle (only those with index `>= first_partner` other than `i` itself).
+    fn predict(&mut self, i: usize, particles: &[Particle], container: &ParticleContainer, first_partner: usize) {
+        let particle = &particles[i];
+        let (x, y) = self.position_now(particles, i);
+        let (vx, vy) = (particle.velocity.x as f64, particle.velocity.y as f64);
+        let radius = particle.radius as f64;
+        let bounds = container.boundaries;
+
+        let push = |queue: &mut BinaryHeap<Event>, delay: Option<f64>, target: Target, counts: (u32, u32)| {
+            if let Some(delay) = delay {
+                queue.push(Event { time: self.time + delay, particle: i, target, counts });
+            }
+        };
+        let own = self.counts[i];
+
+        // Discs bounce when their edge touches a wall, but cross a periodic wall with their centre.
+        let wall_x = if vx > 0.0 { Wall::Right } else { Wall::Left };
+        let reach_x = if container.periodic_x { 0.0 } else { radius };
+        let to_wall_x = time_to_wall(x, vx, bounds.x as f64 + reach_x, bounds.w as f64 - reach_x);
+        push(&mut self.queue, to_wall_x, Target::Wall(wall_x), (own, 0));
+        let wall_y = if vy > 0.0 { Wall::Ground } else { Wall::Ceiling };
+        let reach_y = if container.periodic_y { 0.0 } else { radius };
+        let to_wall_y = time_to_wall(y, vy, bounds.y as f64 + reach_y, bounds.z as f64 - reach_y);
+        push(&mut self.queue, to_wall_y, Target::Wall(wall_y), (own, 0));
+
+        // A partition is reached when the leading edge of the disc touches it. Events for the
+        // partitions beyond the nearest one go stale as soon as that one is reached.
+        for (index, partition) in container.partitions.iter().enumerate() {
+            let wall = partition.x as f64;
+            let to_partition = if vx > 0.0 && x + radius < wall - PARTITION_TOLERANCE {
+                Some((wall - radius - x) / vx)
+            } else if vx < 0.0 && x - radius > wall + PARTITION_TOLERANCE {
+                Some((wall + radius - x) / vx)
+            } else {
+                None
+            };
+            push(&mut self.queue, to_partition, Target::Partition(index), (own, 0));
+        }
+
+        for j in first_partner..particles.len() {
+            if j == i {
+                continue;
+            }
+            let delay = self.time_to_collision(particles, container, i, j);
+            push(&mut self.queue, delay, Target::Particle(j), (own, self.counts[j]));
+        }
+    }
+
+    /// Time until discs `i` and `j` touch, if they ever do on their current paths.
+    ///
+    /// Along periodic axes `j` is replaced by its image nearest to `i` at the current time.
+    fn time_to_collision(&self, particles: &[Particle], container: &ParticleContainer, i: usize, j: usize) -> Option<f64> {
+        let (xi, yi) = self.position_now(particles, i);
+        let (xj, yj) = self.position_now(particles, j);
+        let offset = container.image_offset(
+            Vector2 { x: xi as f32, y: yi as f32 },
+            Vector2 { x: xj as f32, y: yj as f32 },
+        );
+        let (dx, dy) = (xj + offset.x as f64 - xi, yj + offset.y as f64 - yi);
+        let dvx = (particles[j].velocity.x - particles[i].velocity.x) as f64;
+        let dvy = (particles[j].velocity.y - particles[i].velocity.y) as f64;
+        let sigma = (particles[i].radius + particles[j].radius) as f64;
+
+        let dvdr = dx * dvx + dy * dvy;
+        if dvdr >= 0.0 {
+            return None;
+        }
+        let drdr = dx * dx + dy * dy;
+        // Already overlapping and approaching: collide immediately to push them apart.
+        if drdr < sigma * sigma {
+            return Some(0.0);
+        }
+        let dvdv = dvx * dvx + dvy * dvy;
+        let discriminant = dvdr * dvdr - dvdv * (drdr - sigma * sigma);
+        if discriminant < 0.0 {
+            return None;
+        }
+        Some(-(dvdr + discriminant.sqrt()) / dvdv)
+    }
+}
+
+/// Time until a coordinate moving at `velocity` leaves the interval `[low, high]`.
+fn time_to_wall(position: f64, velocity: f64, low: f64, high: f64) -> Option<f64> {
+    if velocity > 0.0 {
+        Some(((high - position) / velocity).max(0.0))
+    } else if velocity < 0.0 {
+        Some(((low - position) / velocity).max(0.0))
+    } else {
+        None
+    }
+}
+
+/// Applies the elastic impulse between touching discs `i` and `j`, accounting for their masses.
+fn bounce(particles: &mut [Particle], container: &ParticleContainer, i: usize, j: usize) {
+    let offset = container.image_offset(particles[i].position, particles[j].position);
+    let dx = particles[j].position.x + offset.x - particles[i].position.x;
+    let dy = particles[j].position.y + offset.y - particles[i].position.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    let inv_mass_i = particles[i].inverse_mass();
+    let inv_mass_j = particles[j].inverse_mass();
+    if distance == 0.0 || inv_mass_i + inv_mass_j == 0.0 {
+        return;
+    }
+    let (nx, ny) = (dx / distance, dy / distance);
+    let dvx = particles[j].velocity.x - particles[i].velocity.x;
+    let dvy = particles[j].velocity.y - particles[i].velocity.y;
+
+    // J = 2 (Δv · n) / (1/m_i + 1/m_j), negative while approaching.
+    let impulse = 2.0 * (dvx * nx + dvy * ny) / (inv_mass_i + inv_mass_j);
+    particles[i].velocity.x += impulse * inv_mass_i * nx;
+    particles[i].velocity.y += impulse * inv_mass_i * ny;
+    particles[j].velocity.x -= impulse * inv_mass_j * nx;
+    particles[j].velocity.y -= impulse * inv_mass_j * ny;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::scenario::Scenario;
+    use crate::physics::world::World;
+
+    #[test]
+    fn hard_discs_conserve_energy_and_never_overlap() {
+        let mut world = World::new(1000.0, 700.0, Scenario::ideal_gas(200));
+        world.toggle_event_driven();
+        let initial_energy = world.kinetic_energy();
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+            for (i, a) in world.particles.iter().enumerate() {
+                for b in &world.particles[i + 1..] {
+                    let distance = ((b.position.x - a.position.x).powi(2) + (b.position.y - a.position.y).powi(2)).sqrt();
+                    assert!(distance > a.radius + b.radius - 1e-2, "{} < {}", distance, a.radius + b.radius);
+                }
+            }
+        }
+        assert!(world.event_driven.is_some());
+        assert!((world.kinetic_energy() / initial_energy - 1.0).abs() < 1e-4);
+    }
+}
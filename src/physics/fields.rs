@@ -0,0 +1,141 @@
+use mint::{Vector2, Vector4};
+use crate::physics::particles::Particle;
+
+
+/// A force acting on each particle individually, independent of the other particles.
+///
+/// Fields are applied to `Particle::force` after it is reset and before the integrator uses it,
+/// so they are re-evaluated as often as the pairwise force laws.
+pub trait ExternalField {
+    /// Adds the field's force on `particle` to `particle.force`.
+    fn apply(&self, particle: &mut Particle);
+
+    /// Returns the potential energy of `particle` in the field, used to track the total energy.
+    fn potential_energy(&self, _particle: &Particle) -> f32 {
+        0.0
+    }
+}
+
+/// Uniform gravitational acceleration `g`, giving each particle the force `m g`.
+///
+/// Screen coordinates grow downwards, so `g = (0, 30)` pulls particles towards the ground.
+pub struct UniformGravity {
+    pub g: Vector2<f32>,
+}
+
+impl ExternalField for UniformGravity {
+    fn apply(&self, particle: &mut Particle) {
+        particle.force.x += particle.mass * self.g.x;
+        particle.force.y += particle.mass * self.g.y;
+    }
+
+    fn potential_energy(&self, particle: &Particle) -> f32 {
+        -particle.mass * (self.g.x * particle.position.x + self.g.y * particle.position.y)
+    }
+}
+
+/// Uniform electric field `E`, giving each particle the force `q E`.
+pub struct UniformElectricField {
+    pub e: Vector2<f32>,
+}
+
+impl ExternalField for UniformElectricField {
+    fn apply(&self, particle: &mut Particle) {
+        particle.force.x += particle.charge * self.e.x;
+        particle.force.y += particle.charge * self.e.y;
+    }
+
+    fn potential_energy(&self, particle: &Particle) -> f32 {
+        -particle.charge * (self.e.x * particle.position.x + self.e.y * particle.position.y)
+    }
+}
+
+/// Isotropic harmonic trap pulling each particle towards `centre` with force `-k (x - centre)`.
+pub struct HarmonicTrap {
+    pub centre: Vector2<f32>,
+    pub stiffness: f32,
+}
+
+impl ExternalField for HarmonicTrap {
+    fn apply(&self, particle: &mut Particle) {
+        particle.force.x -= self.stiffness * (particle.position.x - self.centre.x);
+        particle.force.y -= self.stiffness * (particle.position.y - self.centre.y);
+    }
+
+    fn potential_energy(&self, particle: &Particle) -> f32 {
+        let dx = particle.position.x - self.centre.x;
+        let dy = particle.position.y - self.centre.y;
+        0.5 * self.stiffness * (dx * dx + dy * dy)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FieldType {
+    UniformGravity(Vector2<f32>),
+    UniformElectricField(Vector2<f32>),
+    /// A harmonic trap whose `centre` is given as fractions of the container width and height,
+    /// so it can be described before the size of the box is known.
+    HarmonicTrap { centre: Vector2<f32>, stiffness: f32 },
+}
+
+/// Creates and returns an external field instance based on the given `FieldType`, placed in
+/// the box `boundaries`.
+pub fn build_external_field(field_type: FieldType, boundaries: Vector4<f32>) -> Box<dyn ExternalField> {
+    match field_type {
+        FieldType::UniformGravity(g) => Box::new(UniformGravity { g }),
+        FieldType::UniformElectricField(e) => Box::new(UniformElectricField { e }),
+        FieldType::HarmonicTrap { centre, stiffness } => {
+            let centre = Vector2 {
+                x: boundaries.x + centre.x * (boundaries.w - boundaries.x),
+                y: boundaries.y + centre.y * (boundaries.z - boundaries.y),
+            };
+            Box::new(HarmonicTrap { centre, stiffness })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::container::TEST_BOUNDARIES;
+    use super::*;
+
+    fn particle(x: f32, y: f32) -> Particle {
+        Particle { charge: -0.5, mass: 2.0, ..Particle::at(x, y, 0.0, 0.0) }
+    }
+
+    /// Checks that the field's force is minus the gradient of its potential energy.
+    fn assert_conservative(field_type: FieldType) {
+        let field = build_external_field(field_type, TEST_BOUNDARIES);
+        let h = 0.5;
+        for (x, y) in [(100.0, 200.0), (640.0, 30.0), (900.0, 700.0)] {
+            let mut p = particle(x, y);
+            field.apply(&mut p);
+            let dudx = (field.potential_energy(&particle(x + h, y)) - field.potential_energy(&particle(x - h, y))) / (2.0 * h);
+            let dudy = (field.potential_energy(&particle(x, y + h)) - field.potential_energy(&particle(x, y - h))) / (2.0 * h);
+            assert!((p.force.x + dudx).abs() < 1e-2 * (1.0 + p.force.x.abs()), "{:?}: {} vs {}", field_type, p.force.x, -dudx);
+            assert!((p.force.y + dudy).abs() < 1e-2 * (1.0 + p.force.y.abs()), "{:?}: {} vs {}", field_type, p.force.y, -dudy);
+        }
+    }
+
+    #[test]
+    fn electric_field_pushes_along_e_for_positive_charges() {
+        let field = build_external_field(FieldType::UniformElectricField(Vector2 { x: 3.0, y: -1.0 }), TEST_BOUNDARIES);
+        let mut p = particle(10.0, 10.0);
+        p.charge = 2.0;
+        field.apply(&mut p);
+        assert_eq!((p.force.x, p.force.y), (6.0, -2.0));
+        assert_conservative(FieldType::UniformElectricField(Vector2 { x: 3.0, y: -1.0 }));
+    }
+
+    #[test]
+    fn harmonic_trap_pulls_towards_its_centre() {
+        // At (500, 350), the middle of the 1000 x 700 box.
+        let trap = FieldType::HarmonicTrap { centre: Vector2 { x: 0.5, y: 0.5 }, stiffness: 0.5 };
+        let field = build_external_field(trap, TEST_BOUNDARIES);
+        let mut p = particle(600.0, 250.0);
+        field.apply(&mut p);
+        assert_eq!((p.force.x, p.force.y), (-50.0, 50.0));
+        assert_eq!(field.potential_energy(&particle(500.0, 350.0)), 0.0);
+        assert_conservative(trap);
+    }
+}
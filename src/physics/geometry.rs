@@ -0,0 +1,289 @@
+use std::f32::consts::{PI, TAU};
+use mint::{Vector2, Vector4};
+use crate::physics::particles::Particle;
+
+
+/// A piece of fixed geometry inside the container. Particles bounce off either side of it, so
+/// the same shapes describe both curved or slanted container walls and obstacles in the box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+    /// The straight line segment from `start` to `end`.
+    Segment { start: Vector2<f32>, end: Vector2<f32> },
+    /// The arc of the circle of `radius` around `centre` swept from angle `start` to angle `end`,
+    /// in radians increasing from +x towards +y. `0..TAU` is the full circle.
+    Arc { centre: Vector2<f32>, radius: f32, start: f32, end: f32 },
+}
+
+impl Shape {
+    /// The full circle of `radius` around `centre`: a round container, or a fixed disc.
+    pub fn circle(centre: Vector2<f32>, radius: f32) -> Shape {
+        Shape::Arc { centre, radius, start: 0.0, end: TAU }
+    }
+
+    /// The point of the shape closest to `point`.
+    pub fn closest_point(&self, point: Vector2<f32>) -> Vector2<f32> {
+        match *self {
+            Shape::Segment { start, end } => {
+                let (ex, ey) = (end.x - start.x, end.y - start.y);
+                let length_sq = ex * ex + ey * ey;
+                let t = if length_sq > 0.0 {
+                    (((point.x - start.x) * ex + (point.y - start.y) * ey) / length_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                Vector2 { x: start.x + t * ex, y: start.y + t * ey }
+            }
+            Shape::Arc { centre, radius, start, end } => {
+                let angle = (point.y - centre.y).atan2(point.x - centre.x);
+                if within_arc(angle, start, end) && (point.x != centre.x || point.y != centre.y) {
+                    return on_circle(centre, radius, angle);
+                }
+                // Outside the swept angle, the nearest point is one of the two ends.
+                let first = on_circle(centre, radius, start);
+                let last = on_circle(centre, radius, end);
+                if distance_sq(point, first) <= distance_sq(point, last) { first } else { last }
+            }
+        }
+    }
+
+    /// Pushes `particle` out of the shape and reflects its velocity about the contact normal if
+    /// it is moving into the shape. Returns whether the particle was touching it.
+    pub fn collide(&self, particle: &mut Particle) -> bool {
+        let contact = self.closest_point(particle.position);
+        let dx = particle.position.x - contact.x;
+        let dy = particle.position.y - contact.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        // A centre exactly on the shape has no side to be pushed to.
+        if distance >= particle.radius || distance == 0.0 {
+            return false;
+        }
+
+        let (nx, ny) = (dx / distance, dy / distance);
+        particle.position.x = contact.x + nx * particle.radius;
+        particle.position.y = contact.y + ny * particle.radius;
+        let normal_speed = particle.velocity.x * nx + particle.velocity.y * ny;
+        if normal_speed < 0.0 {
+            particle.velocity.x -= 2.0 * normal_speed * nx;
+            particle.velocity.y -= 2.0 * normal_speed * ny;
+        }
+        true
+    }
+
+    /// Number of times the segment from `a` to `b` crosses the shape.
+    fn crossings(&self, a: Vector2<f32>, b: Vector2<f32>) -> usize {
+        match *self {
+            Shape::Segment { start, end } => {
+                let d1 = cross(start, end, a);
+                let d2 = cross(start, end, b);
+                let d3 = cross(a, b, start);
+                let d4 = cross(a, b, end);
+                ((d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)) as usize
+            }
+            Shape::Arc { centre, radius, start, end } => {
+                // Solve |a + t (b - a) - centre| = radius for t in [0, 1].
+                let (ex, ey) = (b.x - a.x, b.y - a.y);
+                let (fx, fy) = (a.x - centre.x, a.y - centre.y);
+                let qa = ex * ex + ey * ey;
+                let qb = 2.0 * (fx * ex + fy * ey);
+                let qc = fx * fx + fy * fy - radius * radius;
+                let discriminant = qb * qb - 4.0 * qa * qc;
+                if qa == 0.0 || discriminant <= 0.0 {
+                    return 0;
+                }
+                let root = discriminant.sqrt();
+                [(-qb - root) / (2.0 * qa), (-qb + root) / (2.0 * qa)]
+                    .iter()
+                    .filter(|&&t| (0.0..=1.0).contains(&t))
+                    .filter(|&&t| within_arc((fy + t * ey).atan2(fx + t * ex), start, end))
+                    .count()
+            }
+        }
+    }
+}
+
+fn on_circle(centre: Vector2<f32>, radius: f32, angle: f32) -> Vector2<f32> {
+    Vector2 { x: centre.x + radius * angle.cos(), y: centre.y + radius * angle.sin() }
+}
+
+/// Whether `angle` lies in the sweep from `start` to `end`.
+fn within_arc(angle: f32, start: f32, end: f32) -> bool {
+    end - start >= TAU || (angle - start).rem_euclid(TAU) <= end - start
+}
+
+fn distance_sq(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+/// Z component of `(b - a) x (p - a)`: which side of the line through `a` and `b` `p` is on.
+fn cross(a: Vector2<f32>, b: Vector2<f32>, p: Vector2<f32>) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// The closed polygon through `points`, as one segment per edge.
+pub fn polygon(points: &[Vector2<f32>]) -> Vec<Shape> {
+    (0..points.len())
+        .map(|i| Shape::Segment { start: points[i], end: points[(i + 1) % points.len()] })
+        .collect()
+}
+
+/// The regular polygon with `sides` corners on the circle of `radius` around `centre`.
+pub fn regular_polygon(centre: Vector2<f32>, radius: f32, sides: usize) -> Vec<Shape> {
+    let points: Vec<Vector2<f32>> = (0..sides)
+        .map(|i| on_circle(centre, radius, -0.5 * PI + TAU * i as f32 / sides as f32))
+        .collect();
+    polygon(&points)
+}
+
+/// Whether `particle` sits in the same region as `reference` without touching any shape.
+///
+/// Regions are told apart by the parity of the shapes crossed on the way to `reference`, which
+/// is exact for closed outlines and lets new particles avoid pockets and obstacle interiors.
+pub fn is_open(shapes: &[Shape], reference: Vector2<f32>, particle: &Particle) -> bool {
+    let crossings: usize = shapes.iter().map(|shape| shape.crossings(particle.position, reference)).sum();
+    let clear = shapes
+        .iter()
+        .all(|shape| distance_sq(shape.closest_point(particle.position), particle.position) >= particle.radius.powi(2));
+    crossings.is_multiple_of(2) && clear
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GeometryType {
+    /// The plain rectangular box.
+    Box,
+    /// A round container inscribed in the box.
+    Circle,
+    /// Two triangular chambers meeting at a neck of the given height on the middle wall.
+    Hourglass { neck: f32 },
+    /// The left chamber narrows towards an opening of the given height on the middle wall.
+    Funnel { opening: f32 },
+    /// The box with fixed discs and polygons scattered around the middle wall.
+    Obstacles,
+}
+
+/// Creates the shapes of a `GeometryType` for the box `boundaries`.
+///
+/// Every layout leaves the centre of the box open, which `World` uses as the reference point
+/// when placing particles.
+pub fn build_geometry(geometry_type: GeometryType, boundaries: Vector4<f32>) -> Vec<Shape> {
+    let (left, top, right, bottom) = (boundaries.x, boundaries.y, boundaries.w, boundaries.z);
+    let centre = Vector2 { x: 0.5 * (left + right), y: 0.5 * (top + bottom) };
+    let point = |x: f32, y: f32| Vector2 { x, y };
+    match geometry_type {
+        GeometryType::Box => Vec::new(),
+        GeometryType::Circle => {
+            let radius = 0.5 * (right - left).min(bottom - top);
+            vec![Shape::circle(centre, radius)]
+        }
+        GeometryType::Hourglass { neck } => {
+            let upper = point(centre.x, centre.y - 0.5 * neck);
+            let lower = point(centre.x, centre.y + 0.5 * neck);
+            vec![
+                Shape::Segment { start: point(left, top), end: upper },
+                Shape::Segment { start: upper, end: point(right, top) },
+                Shape::Segment { start: point(left, bottom), end: lower },
+                Shape::Segment { start: lower, end: point(right, bottom) },
+            ]
+        }
+        GeometryType::Funnel { opening } => vec![
+            Shape::Segment { start: point(left, top), end: point(centre.x, centre.y - 0.5 * opening) },
+            Shape::Segment { start: point(left, bottom), end: point(centre.x, centre.y + 0.5 * opening) },
+        ],
+        GeometryType::Obstacles => {
+            let size = 0.08 * (right - left).min(bottom - top);
+            let (dx, dy) = (0.25 * (right - left), 0.25 * (bottom - top));
+            let mut shapes = vec![
+                Shape::circle(point(centre.x - dx, centre.y - dy), size),
+                Shape::circle(point(centre.x + dx, centre.y + dy), size),
+            ];
+            shapes.extend(regular_polygon(point(centre.x + dx, centre.y - dy), size, 3));
+            shapes.extend(regular_polygon(point(centre.x - dx, centre.y + dy), size, 4));
+            shapes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::container::TEST_BOUNDARIES;
+    use super::*;
+
+    fn distance_to(shape: &Shape, particle: &Particle) -> f32 {
+        distance_sq(shape.closest_point(particle.position), particle.position).sqrt()
+    }
+
+    #[test]
+    fn a_slanted_segment_mirrors_the_velocity_about_its_normal() {
+        let segment = Shape::Segment { start: Vector2 { x: 0.0, y: 0.0 }, end: Vector2 { x: 100.0, y: 100.0 } };
+        let mut particle = Particle::at(50.0, 53.0, 10.0, -30.0);
+        assert!(segment.collide(&mut particle));
+        // The normal is (-1, 1) / sqrt(2): the normal component flips, the tangential one stays.
+        assert!((particle.velocity.x + 30.0).abs() < 1e-4 && (particle.velocity.y - 10.0).abs() < 1e-4);
+        assert!((distance_to(&segment, &particle) - particle.radius).abs() < 1e-4);
+        assert!(particle.position.y > particle.position.x);
+
+        // Moving away already, the particle is pushed out but keeps its velocity.
+        let mut leaving = Particle::at(50.0, 53.0, -10.0, 30.0);
+        assert!(segment.collide(&mut leaving));
+        assert_eq!((leaving.velocity.x, leaving.velocity.y), (-10.0, 30.0));
+        assert!(!segment.collide(&mut Particle::at(50.0, 60.0, 10.0, -30.0)));
+    }
+
+    #[test]
+    fn circles_bounce_particles_on_either_side() {
+        let circle = Shape::circle(Vector2 { x: 500.0, y: 350.0 }, 300.0);
+
+        let mut inside = Particle::at(797.0, 350.0, 50.0, 20.0);
+        assert!(circle.collide(&mut inside));
+        assert!((inside.position.x - 795.0).abs() < 1e-3 && (inside.position.y - 350.0).abs() < 1e-3);
+        assert!((inside.velocity.x + 50.0).abs() < 1e-3 && (inside.velocity.y - 20.0).abs() < 1e-3);
+
+        let mut outside = Particle::at(500.0, 47.0, 20.0, 50.0);
+        assert!(circle.collide(&mut outside));
+        assert!((outside.position.x - 500.0).abs() < 1e-3 && (outside.position.y - 45.0).abs() < 1e-3);
+        assert!((outside.velocity.x - 20.0).abs() < 1e-3 && (outside.velocity.y + 50.0).abs() < 1e-3);
+
+        // Past the end of a half circle, only its end point can be hit.
+        let arc = Shape::Arc { centre: Vector2 { x: 500.0, y: 350.0 }, radius: 300.0, start: 0.0, end: PI };
+        assert!(!arc.collide(&mut Particle::at(500.0, 53.0, 0.0, -50.0)));
+        let mut at_end = Particle::at(197.0, 347.0, 0.0, 50.0);
+        assert!(arc.collide(&mut at_end));
+        assert!((distance_to(&arc, &at_end) - at_end.radius).abs() < 1e-3);
+    }
+
+    #[test]
+    fn partial_arcs_can_wrap_past_pi() {
+        // The left-hand quarter, from 135 to 225 degrees, written both ways round.
+        for (start, end) in [(0.75 * PI, 1.25 * PI), (-1.25 * PI, -0.75 * PI)] {
+            for angle in [PI, -PI, 0.8 * PI, -0.8 * PI] {
+                assert!(within_arc(angle, start, end), "{} in {}..{}", angle, start, end);
+            }
+            for angle in [0.0, 0.5 * PI, -0.5 * PI, 0.7 * PI, -0.7 * PI] {
+                assert!(!within_arc(angle, start, end), "{} in {}..{}", angle, start, end);
+            }
+        }
+        assert!(within_arc(0.3, 0.0, TAU) && within_arc(-3.0, 0.0, TAU));
+    }
+
+    #[test]
+    fn obstacle_interiors_and_hourglass_pockets_are_not_open() {
+        let centre = Vector2 { x: 500.0, y: 350.0 };
+        let obstacles = build_geometry(GeometryType::Obstacles, TEST_BOUNDARIES);
+        // A disc of radius 56 sits at (250, 175) and a square at (250, 525).
+        assert!(!is_open(&obstacles, centre, &Particle::at(250.0, 175.0, 0.0, 0.0)));
+        assert!(!is_open(&obstacles, centre, &Particle::at(250.0, 525.0, 0.0, 0.0)));
+        assert!(!is_open(&obstacles, centre, &Particle::at(250.0, 233.0, 0.0, 0.0)));
+        assert!(is_open(&obstacles, centre, &Particle::at(100.0, 350.0, 0.0, 0.0)));
+
+        // The neck spans y = 300 to 400; the pockets lie above and below the funnels.
+        let hourglass = build_geometry(GeometryType::Hourglass { neck: 100.0 }, TEST_BOUNDARIES);
+        assert!(!is_open(&hourglass, centre, &Particle::at(400.0, 20.0, 0.0, 0.0)));
+        assert!(!is_open(&hourglass, centre, &Particle::at(700.0, 680.0, 0.0, 0.0)));
+        assert!(is_open(&hourglass, centre, &Particle::at(100.0, 350.0, 0.0, 0.0)));
+        assert!(is_open(&hourglass, centre, &Particle::at(900.0, 350.0, 0.0, 0.0)));
+
+        for geometry_type in [GeometryType::Box, GeometryType::Circle, GeometryType::Funnel { opening: 100.0 }] {
+            let shapes = build_geometry(geometry_type, TEST_BOUNDARIES);
+            assert!(is_open(&shapes, centre, &Particle::at(500.0, 350.0, 0.0, 0.0)));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use rand::Rng;
+use crate::physics::particles::Particle;
+use crate::physics::utils::{random_gaussian, random_vector, SimRng};
+
+
+/// How charges are assigned to freshly created particles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChargeDistribution {
+    /// Every particle is uncharged.
+    Neutral,
+    /// Every particle carries the same charge.
+    Uniform(f32),
+    /// Equal populations of `+q` and `-q`, so the box is neutral overall.
+    /// With an odd particle count the last particle is left uncharged.
+    NeutralPlasma(f32),
+    /// Charges drawn uniformly from `[min, max)`.
+    Random { min: f32, max: f32 },
+}
+
+/// Assigns a charge to every particle according to `distribution`.
+pub fn assign_charges(particles: &mut [Particle], distribution: ChargeDistribution, rng: &mut SimRng) {
+    let len = particles.len();
+    for (index, particle) in particles.iter_mut().enumerate() {
+        particle.charge = match distribution {
+            ChargeDistribution::Neutral => 0.0,
+            ChargeDistribution::Uniform(q) => q,
+            ChargeDistribution::NeutralPlasma(q) => {
+                if index == len - 1 && len % 2 == 1 {
+                    0.0
+                } else if index % 2 == 0 {
+                    q
+                } else {
+                    -q
+                }
+            }
+            ChargeDistribution::Random { min, max } => rng.gen_range(min..max),
+        };
+    }
+}
+
+/// A few heavy, large particles mixed in with the gas, whose slow jiggling under the impacts of
+/// the light particles is Brownian motion.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tracers {
+    /// Number of tracers, taken from the start of the particle list.
+    pub count: u32,
+    pub mass: f32,
+    pub radius: f32,
+}
+
+/// How velocities are assigned to freshly created particles.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VelocityDistribution {
+    /// Speeds drawn uniformly from `[min, max)` in random directions, regardless of mass.
+    Uniform { min: f32, max: f32 },
+    /// The 2D Maxwell–Boltzmann distribution at `temperature`: each velocity component is
+    /// Gaussian with variance `kT / m`, so immovable particles stay at rest. With `remove_drift`
+    /// the net momentum is subtracted, and with `exact_energy` the velocities are then rescaled
+    /// so the kinetic energy per particle is exactly `temperature`.
+    MaxwellBoltzmann { temperature: f32, remove_drift: bool, exact_energy: bool },
+}
+
+/// Assigns a velocity to every particle according to `distribution`.
+pub fn assign_velocities(particles: &mut [Particle], distribution: VelocityDistribution, rng: &mut SimRng) {
+    match distribution {
+        VelocityDistribution::Uniform { min, max } => {
+            for particle in particles.iter_mut() {
+                particle.velocity = random_vector(max, min, rng);
+            }
+        }
+        VelocityDistribution::MaxwellBoltzmann { temperature, remove_drift, exact_energy } => {
+            for particle in particles.iter_mut() {
+                let sigma = (temperature * particle.inverse_mass()).sqrt();
+                particle.velocity.x = sigma * random_gaussian(rng);
+                particle.velocity.y = sigma * random_gaussian(rng);
+            }
+            // A single particle would be brought to rest, so its drift is left alone.
+            if remove_drift && particles.len() > 1 {
+                remove_net_momentum(particles);
+            }
+            if exact_energy {
+                let count = particles.iter().filter(|particle| particle.inverse_mass() > 0.0).count();
+                let kinetic_energy: f32 = particles.iter().map(|particle| particle.kinetic_energy()).sum();
+                if kinetic_energy > 0.0 {
+                    let scale = (count as f32 * temperature / kinetic_energy).sqrt();
+                    for particle in particles.iter_mut() {
+                        particle.velocity.x *= scale;
+                        particle.velocity.y *= scale;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shifts the velocities of the movable particles so that their total momentum is zero.
+fn remove_net_momentum(particles: &mut [Particle]) {
+    let movable = || particles.iter().filter(|particle| particle.inverse_mass() > 0.0);
+    let total_mass: f32 = movable().map(|particle| particle.mass).sum();
+    if total_mass == 0.0 {
+        return;
+    }
+    let drift_x = movable().map(|particle| particle.mass * particle.velocity.x).sum::<f32>() / total_mass;
+    let drift_y = movable().map(|particle| particle.mass * particle.velocity.y).sum::<f32>() / total_mass;
+    for particle in particles.iter_mut().filter(|particle| particle.inverse_mass() > 0.0) {
+        particle.velocity.x -= drift_x;
+        particle.velocity.y -= drift_y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::physics::container::{ParticleContainer, TEST_BOUNDARIES};
+    use super::*;
+
+    fn charged(distribution: ChargeDistribution, num: usize) -> Vec<f32> {
+        let mut rng = SimRng::seed_from_u64(3);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut particles: Vec<Particle> = (0..num).map(|_| Particle::new(&container, &mut rng)).collect();
+        assign_charges(&mut particles, distribution, &mut rng);
+        particles.iter().map(|particle| particle.charge).collect()
+    }
+
+    #[test]
+    fn uniform_charges_are_all_equal() {
+        assert!(charged(ChargeDistribution::Uniform(0.5), 11).iter().all(|&q| q == 0.5));
+    }
+
+    #[test]
+    fn neutral_plasma_has_no_net_charge() {
+        for num in [10, 11] {
+            assert_eq!(charged(ChargeDistribution::NeutralPlasma(0.5), num).iter().sum::<f32>(), 0.0);
+        }
+    }
+
+    #[test]
+    fn random_charges_stay_in_range_and_differ() {
+        let charges = charged(ChargeDistribution::Random { min: -1.0, max: 2.0 }, 200);
+        assert!(charges.iter().all(|&q| (-1.0..2.0).contains(&q)));
+        assert!(charges.iter().any(|&q| q < 0.0) && charges.iter().any(|&q| q > 1.0));
+    }
+
+    #[test]
+    fn maxwell_boltzmann_velocities_have_no_drift_the_exact_energy_and_rayleigh_speeds() {
+        let mut rng = SimRng::seed_from_u64(3);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut particles: Vec<Particle> = (0..5000).map(|_| Particle::new(&container, &mut rng)).collect();
+        let temperature = 10000.0;
+        let distribution = VelocityDistribution::MaxwellBoltzmann { temperature, remove_drift: true, exact_energy: true };
+        assign_velocities(&mut particles, distribution, &mut rng);
+
+        // The net momentum vanishes up to rounding, measured against the momenta that cancel.
+        let momentum_x: f32 = particles.iter().map(|p| p.mass * p.velocity.x).sum();
+        let momentum_y: f32 = particles.iter().map(|p| p.mass * p.velocity.y).sum();
+        let scale: f32 = particles.iter().map(|p| p.mass * p.speed()).sum();
+        assert!(momentum_x.abs() < 1e-6 * scale && momentum_y.abs() < 1e-6 * scale, "{} {}", momentum_x, momentum_y);
+        let energy_per_particle = particles.iter().map(|p| p.kinetic_energy()).sum::<f32>() / particles.len() as f32;
+        assert!((energy_per_particle / temperature - 1.0).abs() < 1e-4, "{}", energy_per_particle);
+
+        // In 2D the speeds follow the Rayleigh distribution, P(v < s) = 1 - exp(-s² / 2σ²) with
+        // σ² = kT / m. Compare the share of particles in bins half a σ wide.
+        let sigma = temperature.sqrt();
+        for bin in 0..6 {
+            let (low, high) = (0.5 * bin as f32, 0.5 * (bin + 1) as f32);
+            let expected = (-0.5 * low * low).exp() - (-0.5 * high * high).exp();
+            let share = particles.iter().filter(|p| (low..high).contains(&(p.speed() / sigma))).count() as f32 / particles.len() as f32;
+            assert!((share - expected).abs() < 0.02, "bin {}: {} vs {}", bin, share, expected);
+        }
+    }
+}
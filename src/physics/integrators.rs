@@ -0,0 +1,187 @@
+use mint::Vector2;
+use crate::physics::particles::Particle;
+
+
+/// A time-stepping scheme for the particle equations of motion.
+///
+/// The integrator owns the order in which positions, velocities and forces are updated.
+/// `forces` recomputes `Particle::force` for the current positions and velocities; each scheme
+/// calls it as many times per step as it needs, so schemes can be compared fairly.
+pub trait Integrator {
+    /// Advances `particles` by `dt`.
+    ///
+    /// `fresh` tells the scheme that `Particle::force` already holds the forces at the current
+    /// positions. Returns whether it still does after the step, so the next step can reuse them.
+    fn step(&self, particles: &mut [Particle], dt: f32, forces: &mut dyn FnMut(&mut [Particle]), fresh: bool) -> bool;
+}
+
+/// Semi-implicit (symplectic) Euler: `v += a dt`, then `x += v dt`. One force evaluation per step.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn step(&self, particles: &mut [Particle], dt: f32, forces: &mut dyn FnMut(&mut [Particle]), fresh: bool) -> bool {
+        if !fresh {
+            forces(particles);
+        }
+        for particle in particles.iter_mut() {
+            particle.update(dt);
+        }
+        false
+    }
+}
+
+/// Velocity Verlet (kick-drift-kick leapfrog). Symplectic and second order; one force
+/// evaluation per step, as the forces at the end of a step start the next one.
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step(&self, particles: &mut [Particle], dt: f32, forces: &mut dyn FnMut(&mut [Particle]), fresh: bool) -> bool {
+        if !fresh {
+            forces(particles);
+        }
+        for particle in particles.iter_mut() {
+            particle.update_velocity(0.5 * dt);
+            particle.update_position(dt);
+        }
+        forces(particles);
+        for particle in particles.iter_mut() {
+            particle.update_velocity(0.5 * dt);
+        }
+        true
+    }
+}
+
+/// Classical fourth-order Runge–Kutta. Very accurate over a single step but not symplectic;
+/// four force evaluations per step.
+pub struct RungeKutta4;
+
+impl Integrator for RungeKutta4 {
+    fn step(&self, particles: &mut [Particle], dt: f32, forces: &mut dyn FnMut(&mut [Particle]), fresh: bool) -> bool {
+        let x0: Vec<Vector2<f32>> = particles.iter().map(|p| p.position).collect();
+        let v0: Vec<Vector2<f32>> = particles.iter().map(|p| p.velocity).collect();
+        let zero = Vector2 { x: 0.0, y: 0.0 };
+        let mut dx = vec![zero; particles.len()];
+        let mut dv = vec![zero; particles.len()];
+
+        // Stage weights of the final combination, and the offset at which the next stage is evaluated.
+        let stages = [(1.0 / 6.0, 0.5), (1.0 / 3.0, 0.5), (1.0 / 3.0, 1.0), (1.0 / 6.0, 0.0)];
+        for (stage, (weight, next_offset)) in stages.into_iter().enumerate() {
+            if stage > 0 || !fresh {
+                forces(particles);
+            }
+            for (i, particle) in particles.iter_mut().enumerate() {
+                // Derivatives at this stage: dx/dt = v, dv/dt = a.
+                let k_x = particle.velocity;
+                let k_v = particle.acceleration();
+                dx[i].x += weight * k_x.x;
+                dx[i].y += weight * k_x.y;
+                dv[i].x += weight * k_v.x;
+                dv[i].y += weight * k_v.y;
+
+                particle.position.x = x0[i].x + next_offset * dt * k_x.x;
+                particle.position.y = x0[i].y + next_offset * dt * k_x.y;
+                particle.velocity.x = v0[i].x + next_offset * dt * k_v.x;
+                particle.velocity.y = v0[i].y + next_offset * dt * k_v.y;
+            }
+        }
+
+        for (i, particle) in particles.iter_mut().enumerate() {
+            particle.position.x = x0[i].x + dt * dx[i].x;
+            particle.position.y = x0[i].y + dt * dx[i].y;
+            particle.velocity.x = v0[i].x + dt * dv[i].x;
+            particle.velocity.y = v0[i].y + dt * dv[i].y;
+        }
+        false
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IntegratorType {
+    SemiImplicitEuler,
+    VelocityVerlet,
+    RungeKutta4,
+}
+
+impl IntegratorType {
+    /// The next scheme in the cycle, used by the renderer to switch schemes at runtime.
+    pub fn next(self) -> IntegratorType {
+        match self {
+            IntegratorType::SemiImplicitEuler => IntegratorType::VelocityVerlet,
+            IntegratorType::VelocityVerlet => IntegratorType::RungeKutta4,
+            IntegratorType::RungeKutta4 => IntegratorType::SemiImplicitEuler,
+        }
+    }
+}
+
+/// Creates and returns an integrator instance based on the given `IntegratorType`.
+pub fn build_integrator(integrator_type: IntegratorType) -> Box<dyn Integrator> {
+    match integrator_type {
+        IntegratorType::SemiImplicitEuler => Box::new(SemiImplicitEuler),
+        IntegratorType::VelocityVerlet => Box::new(VelocityVerlet),
+        IntegratorType::RungeKutta4 => Box::new(RungeKutta4),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+    use super::*;
+
+    /// Spring constant of the trap; with unit mass the angular frequency is 1.
+    const STIFFNESS: f32 = 1.0;
+
+    fn harmonic_trap(particles: &mut [Particle]) {
+        for particle in particles.iter_mut() {
+            particle.force = Vector2 { x: -STIFFNESS * particle.position.x, y: -STIFFNESS * particle.position.y };
+        }
+    }
+
+    fn trap_energy(particle: &Particle) -> f32 {
+        let (x, y) = (particle.position.x, particle.position.y);
+        particle.kinetic_energy() + 0.5 * STIFFNESS * (x * x + y * y)
+    }
+
+    /// Distance in phase space from the exact trajectory after one step of `dt`, starting at rest
+    /// at `(100, 0)`.
+    fn one_step_error(integrator: &dyn Integrator, dt: f32) -> f32 {
+        let mut particles = [Particle::at(100.0, 0.0, 0.0, 0.0)];
+        integrator.step(&mut particles, dt, &mut harmonic_trap, false);
+        let (x, v) = (100.0 * dt.cos(), -100.0 * dt.sin());
+        let particle = &particles[0];
+        ((particle.position.x - x).powi(2) + (particle.velocity.x - v).powi(2) + particle.position.y.powi(2)).sqrt()
+    }
+
+    #[test]
+    fn runge_kutta_is_more_accurate_than_euler_over_a_step() {
+        let euler = one_step_error(&SemiImplicitEuler, 0.1);
+        let verlet = one_step_error(&VelocityVerlet, 0.1);
+        let runge_kutta = one_step_error(&RungeKutta4, 0.1);
+        // The local errors are O(dt^2), O(dt^3) and O(dt^5): about 0.5, 0.02 and 1e-4.
+        assert!(verlet < 0.1 * euler, "Verlet {verlet} vs Euler {euler}");
+        assert!(runge_kutta < 0.01 * verlet, "RK4 {runge_kutta} vs Verlet {verlet}");
+    }
+
+    #[test]
+    fn velocity_verlet_keeps_the_energy_error_bounded_over_many_periods() {
+        let dt = 0.1;
+        let steps_per_period = (TAU / dt).round() as usize;
+        let mut particles = [Particle::at(100.0, 0.0, 0.0, 50.0)];
+        let initial = trap_energy(&particles[0]);
+        let mut fresh = false;
+        let mut worst_per_period = Vec::new();
+        for _ in 0..100 {
+            let mut worst: f32 = 0.0;
+            for _ in 0..steps_per_period {
+                fresh = VelocityVerlet.step(&mut particles, dt, &mut harmonic_trap, fresh);
+                worst = worst.max((trap_energy(&particles[0]) / initial - 1.0).abs());
+            }
+            worst_per_period.push(worst);
+        }
+
+        // The energy oscillates by about (omega dt)^2 / 4 around its initial value...
+        assert!(worst_per_period.iter().all(|&worst| worst < 0.005), "{worst_per_period:?}");
+        // ...and does not drift: the last period strays no further than the first.
+        let (first, last) = (worst_per_period[0], worst_per_period[99]);
+        assert!(last < 1.2 * first, "first period {first}, last period {last}");
+    }
+}
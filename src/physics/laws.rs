@@ -1,3 +1,5 @@
+use mint::{Vector2, Vector4};
+use crate::physics::fields::{build_external_field, ExternalField, FieldType};
 use crate::physics::particles::{Particle};
 
 
@@ -21,11 +23,19 @@ use crate::physics::particles::{Particle};
 /// # Examples
 ///
 /// ```rust
-/// // Assuming that CoulombLaw implements PairwiseInteraction:
-/// let mut p1 = Particle::new(/* parameters here */);
-/// let p2 = Particle::new(/* parameters here */);
-/// let law = CoulombLaw::new(/* parameters here */);
-/// p1 = law.resolve(&mut p1, &p2);
+/// use mint::Vector4;
+/// use rand::SeedableRng;
+/// use rusticles::physics::container::ParticleContainer;
+/// use rusticles::physics::laws::{CoulombLaw, InteractionLaw};
+/// use rusticles::physics::particles::Particle;
+/// use rusticles::physics::utils::SimRng;
+///
+/// let container = ParticleContainer::new(Vector4 { x: 0.0, y: 0.0, z: 700.0, w: 1000.0 });
+/// let mut rng = SimRng::seed_from_u64(0);
+/// let mut p1 = Particle::new(&container, &mut rng);
+/// let mut p2 = Particle::new(&container, &mut rng);
+/// let law = CoulombLaw::new(8.9875517923e9, 0.001, 2000.0);
+/// law.resolve(&mut p1, &mut p2);
 /// ```
 ///
 pub trait InteractionLaw {
@@ -40,6 +50,35 @@ pub trait InteractionLaw {
     ///
     /// Returns a `Particle` reflecting the updated state of `obj1` after the interaction.
     fn resolve(&self, obj1: &mut Particle, obj2: &mut Particle) -> bool;
+
+    /// Returns the potential energy stored in the pair with `obj2` shifted by `offset`, e.g. to
+    /// its periodic image, used to track the total energy.
+    ///
+    /// Contact laws have no potential and keep the default of zero.
+    fn potential_energy(&self, _obj1: &Particle, _obj2: &Particle, _offset: Vector2<f32>) -> f32 {
+        0.0
+    }
+
+    /// Whether the law stores any potential energy, so energy sums can skip it otherwise.
+    fn has_potential(&self) -> bool {
+        false
+    }
+
+    /// Whether the law acts through instantaneous impulses on velocities and positions rather
+    /// than through `Particle::force`.
+    ///
+    /// Impulsive laws are resolved once per step; force laws are re-evaluated by the integrator.
+    fn is_impulsive(&self) -> bool {
+        false
+    }
+
+    /// Centre-to-centre distance beyond which the law never acts, or `None` for long-range laws.
+    ///
+    /// Finite-range laws are evaluated through the spatial-grid broad phase. Contact laws return
+    /// `Some(0.0)`: they only act on touching discs, which the grid always covers.
+    fn range(&self) -> Option<f32> {
+        None
+    }
 }
 
 /// A structure representing the Coulomb force interaction between charged particles.
@@ -53,13 +92,12 @@ pub trait InteractionLaw {
 /// * `k` - The Coulomb constant. Typically, this is \(8.9875517923 \times 10^9 \, \text{N·m}^2/\text{C}^2\).
 /// * `softening` - A small value added (squared) to the denominator to avoid singularities when particles are extremely close.
 /// * `cutoff` - A distance threshold beyond which the force is not applied.
-///             (Particles farther apart than this value will not interact.)
+///   (Particles farther apart than this value will not interact.)
 ///
 /// # Example
 ///
 /// ```rust
-/// use crate::physics::coulomb_law::CoulombLaw;
-/// use crate::physics::particles::Particle;
+/// use rusticles::physics::laws::CoulombLaw;
 ///
 /// // Create a new CoulombLaw instance with a given constant, softening parameter, and cutoff.
 /// let coulomb = CoulombLaw::new(8.9875517923e9, 0.001, 100.0);
@@ -88,13 +126,54 @@ impl CoulombLaw {
     /// # Example
     ///
     /// ```rust
-    /// use crate::physics::coulomb_law::CoulombLaw;
+    /// use rusticles::physics::laws::CoulombLaw;
     ///
     /// let coulomb = CoulombLaw::new(8.9875517923e9, 0.001, 100.0);
     /// ```
     pub fn new(k: f32, softening: f32, cutoff: f32) -> CoulombLaw {
         CoulombLaw { k, softening, cutoff }
     }
+
+    /// Squared distance below which the force on `particle` stops growing, so overlapping
+    /// charges do not receive enormous kicks.
+    fn min_distance_sq(particle: &Particle) -> f32 {
+        particle.radius * 0.1
+    }
+
+    /// Computes the force exerted on `particle` by a point charge `source_charge` at `source`.
+    ///
+    /// The source does not have to be a particle: the Barnes–Hut evaluator passes the net charge
+    /// and centre of charge of a whole cell of the tree.
+    ///
+    /// # Returns
+    ///
+    /// The force on `particle`, or zero if the source lies beyond the cutoff.
+    pub fn force_on(&self, particle: &Particle, source_charge: f32, source: Vector2<f32>) -> Vector2<f32> {
+        // Calculate the displacement vector from the particle to the source.
+        let dx = source.x - particle.position.x;
+        let dy = source.y - particle.position.y;
+        let mut distance_sq = dx * dx + dy * dy;
+        // Add softening to avoid singularity.
+        distance_sq += self.softening * self.softening;
+        let distance = distance_sq.sqrt();
+
+        // Apply the cutoff: if the source is too far away, do nothing.
+        if distance > self.cutoff {
+            return Vector2 { x: 0.0, y: 0.0 };
+        }
+
+        distance_sq = distance_sq.max(CoulombLaw::min_distance_sq(particle));
+
+        // Compute the force magnitude.
+        // The sign of the charge product determines whether the force is attractive or repulsive.
+        let force_magnitude = self.k * (particle.charge * source_charge) / distance_sq;
+
+        // For like charges the force is directed away from the source.
+        Vector2 {
+            x: -force_magnitude * dx / distance,
+            y: -force_magnitude * dy / distance,
+        }
+    }
 }
 
 impl InteractionLaw for CoulombLaw {
@@ -113,40 +192,39 @@ impl InteractionLaw for CoulombLaw {
     ///
     /// Returns `true` if the interaction was computed (or skipped due to a zero distance), otherwise `false`.
     fn resolve(&self, particle1: &mut Particle, particle2: &mut Particle) -> bool {
-        // Here we use hard-coded charges for demonstration.
-        // In a full implementation, these would come from the Particle's properties.
-        let p1_charge = 0.001234;
-        let p2_charge = 0.001234;
+        let force = self.force_on(particle1, particle2.charge, particle2.position);
 
-        // Calculate the displacement vector from particle1 to particle2.
-        let dx = particle2.position.x - particle1.position.x;
-        let dy = particle2.position.y - particle1.position.y;
-        let mut distance_sq = dx * dx + dy * dy;
-        // Add softening to avoid singularity.
-        distance_sq += self.softening * self.softening;
-        let distance = distance_sq.sqrt();
+        // Newton's third law: particle2 feels the opposite force.
+        particle1.force.x += force.x;
+        particle1.force.y += force.y;
 
-        // Apply the cutoff: if particles are too far apart, do nothing.
+        particle2.force.x -= force.x;
+        particle2.force.y -= force.y;
+
+        true
+    }
+
+    /// Softened Coulomb potential `k q1 q2 / sqrt(r² + softening²)`, zero beyond the cutoff.
+    ///
+    /// Where `force_on` caps the force on `particle1`, the potential continues linearly, matching
+    /// the capped force.
+    fn potential_energy(&self, particle1: &Particle, particle2: &Particle, offset: Vector2<f32>) -> f32 {
+        let dx = particle2.position.x + offset.x - particle1.position.x;
+        let dy = particle2.position.y + offset.y - particle1.position.y;
+        let distance = (dx * dx + dy * dy + self.softening * self.softening).sqrt();
         if distance > self.cutoff {
-            return true;
+            return 0.0;
         }
-
-        if distance_sq < particle1.radius * 0.1 {
-            distance_sq = particle1.radius * 0.1;
+        let min_distance_sq = CoulombLaw::min_distance_sq(particle1);
+        let min_distance = min_distance_sq.sqrt();
+        let product = self.k * particle1.charge * particle2.charge;
+        if distance < min_distance {
+            return product / min_distance + product / min_distance_sq * (min_distance - distance);
         }
+        product / distance
+    }
 
-        // Compute the force magnitude.
-        // The sign of (p1_charge * p2_charge) will determine whether the force is attractive or repulsive.
-        let force_magnitude = self.k * (p1_charge * p2_charge) / distance_sq;
-
-        // For repulsion between like charges, the force on particle1 should be directed away from particle2.
-        // Thus, subtract the force from particle1 and add it to particle2.
-        particle1.force.x -= force_magnitude * dx / distance;
-        particle1.force.y -= force_magnitude * dy / distance;
-
-        particle2.force.x += force_magnitude * dx / distance;
-        particle2.force.y += force_magnitude * dy / distance;
-
+    fn has_potential(&self) -> bool {
         true
     }
 }
@@ -169,17 +247,32 @@ pub struct ImpulseCollision {
 
 
 impl ImpulseCollision {
+    /// Creates a new instance of `ImpulseCollision`.
+    ///
+    /// # Arguments
+    ///
+    /// * `restitution` - Coefficient of restitution `e` in `[0, 1]`; values outside are clamped.
+    /// * `correction_factor` - Fraction of the penetration depth removed per resolution.
+    /// * `penetration_slop` - Penetration depth tolerated without positional correction.
     pub fn new(restitution: f32, correction_factor: f32, penetration_slop: f32) -> ImpulseCollision {
         ImpulseCollision {
-            restitution: restitution,
-            correction_factor: correction_factor,
-            penetration_slop: penetration_slop
+            restitution: restitution.clamp(0.0, 1.0),
+            correction_factor,
+            penetration_slop
         }
     }
 }
 
 impl InteractionLaw for ImpulseCollision {
-
+    /// Resolves a contact between two discs using the impulse for unequal masses.
+    ///
+    /// The normal impulse `j = (1 + e) (v_rel · n) / (1/m1 + 1/m2)` is applied only while the
+    /// particles approach. Any penetration beyond `penetration_slop` is then reduced by
+    /// `correction_factor`, shared between the particles in proportion to their inverse masses.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the particles overlap, otherwise `false`.
     fn resolve(&self, p1: &mut Particle, p2: &mut Particle) -> bool {
         let dx = p2.position.x - p1.position.x;
         let dy = p2.position.y - p1.position.y;
@@ -199,43 +292,232 @@ impl InteractionLaw for ImpulseCollision {
             // Relative velocity.
             let rvx = p1.velocity.x - p2.velocity.x;
             let rvy = p1.velocity.y - p2.velocity.y;
-            // Dot product of relative velocity and normal.
+            // Dot product of relative velocity and normal; positive when the particles approach.
             let rel_vel_dot_norm = rvx * nx + rvy * ny;
 
-            // Only resolve if particles are moving toward each other.
-            if rel_vel_dot_norm > 0.0 {
+            let inv_mass1 = p1.inverse_mass();
+            let inv_mass2 = p2.inverse_mass();
+            let inv_mass_sum = inv_mass1 + inv_mass2;
+            // Two immovable particles cannot push each other.
+            if inv_mass_sum == 0.0 {
                 return true;
             }
 
-            // For equal mass and perfectly elastic collision:
-            // The impulse scalar (simplified for m1 = m2 = 1).
-            let impulse = rel_vel_dot_norm;
-            // Update velocities.
-            p1.velocity.x -= impulse * nx;
-            p1.velocity.y -= impulse * ny;
-            p2.velocity.x += impulse * nx;
-            p2.velocity.y += impulse * ny;
-
-            // Reposition particles so they are not overlapping.
-            let overlap = 0.5 * (radius_sum - distance);
-            p1.position.x -= overlap * nx;
-            p1.position.y -= overlap * ny;
-            p2.position.x += overlap * nx;
-            p2.position.y += overlap * ny;
+            // Only apply an impulse if particles are moving toward each other.
+            if rel_vel_dot_norm > 0.0 {
+                // j = (1 + e) (v_rel · n) / (1/m1 + 1/m2)
+                let impulse = (1.0 + self.restitution) * rel_vel_dot_norm / inv_mass_sum;
+                // Update velocities.
+                p1.velocity.x -= impulse * inv_mass1 * nx;
+                p1.velocity.y -= impulse * inv_mass1 * ny;
+                p2.velocity.x += impulse * inv_mass2 * nx;
+                p2.velocity.y += impulse * inv_mass2 * ny;
+            }
+
+            // Reposition particles so they are not overlapping, moving the lighter one further.
+            let penetration = radius_sum - distance;
+            let correction = (penetration - self.penetration_slop).max(0.0) * self.correction_factor / inv_mass_sum;
+            p1.position.x -= correction * inv_mass1 * nx;
+            p1.position.y -= correction * inv_mass1 * ny;
+            p2.position.x += correction * inv_mass2 * nx;
+            p2.position.y += correction * inv_mass2 * ny;
             return true;
         }
-        return false;
+        false
+    }
+
+    fn is_impulsive(&self) -> bool {
+        true
+    }
+
+    fn range(&self) -> Option<f32> {
+        Some(0.0)
     }
 }
 
+/// The 12-6 Lennard-Jones interaction, truncated at `cutoff` and shifted so the energy is
+/// continuous there.
+///
+/// `V(r) = 4ε[(σ/r)¹² - (σ/r)⁶] - V(r_c)` for `r < r_c`, and zero beyond. The short-range
+/// repulsion and the longer-range attraction let a gas condense and phase-separate.
+///
+/// # Fields
+///
+/// * `epsilon` - Depth of the potential well.
+/// * `sigma` - Distance at which the unshifted potential crosses zero.
+/// * `cutoff` - Distance beyond which the interaction is ignored, typically `2.5σ`.
+#[derive(Debug, Clone, Copy)]
+pub struct LennardJones {
+    pub epsilon: f32,
+    pub sigma: f32,
+    pub cutoff: f32,
+}
+
+impl LennardJones {
+    pub fn new(epsilon: f32, sigma: f32, cutoff: f32) -> LennardJones {
+        LennardJones { epsilon, sigma, cutoff }
+    }
+
+    /// Separation below which the force stops growing, so randomly placed, overlapping
+    /// particles do not receive enormous kicks.
+    fn min_distance(&self) -> f32 {
+        0.8 * self.sigma
+    }
+
+    /// Magnitude of the pair force at distance `r`, positive when repulsive.
+    fn force(&self, r: f32) -> f32 {
+        let r = r.max(self.min_distance());
+        let sr6 = (self.sigma / r).powi(6);
+        24.0 * self.epsilon / r * (2.0 * sr6 * sr6 - sr6)
+    }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    /// Unshifted pair potential at distance `r`.
+    ///
+    /// Below `min_distance` the potential continues linearly, matching the capped force.
+    fn potential(&self, r: f32) -> f32 {
+        let r_min = self.min_distance();
+        let clamped = r.max(r_min);
+        let sr6 = (self.sigma / clamped).powi(6);
+        4.0 * self.epsilon * (sr6 * sr6 - sr6) + self.force(r_min) * (clamped - r)
+    }
+}
+
+impl InteractionLaw for LennardJones {
+    /// Adds the Lennard-Jones force `F(r) = 24ε/r [2(σ/r)¹² - (σ/r)⁶]` to both particles,
+    /// repulsive when positive.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the pair is within the cutoff, otherwise `false`.
+    fn resolve(&self, particle1: &mut Particle, particle2: &mut Particle) -> bool {
+        let dx = particle2.position.x - particle1.position.x;
+        let dy = particle2.position.y - particle1.position.y;
+        let distance_sq = dx * dx + dy * dy;
+        if distance_sq >= self.cutoff * self.cutoff || distance_sq == 0.0 {
+            return false;
+        }
+        let distance = distance_sq.sqrt();
+        let force_magnitude = self.force(distance);
+
+        // A repulsive force pushes particle1 away from particle2.
+        particle1.force.x -= force_magnitude * dx / distance;
+        particle1.force.y -= force_magnitude * dy / distance;
+        particle2.force.x += force_magnitude * dx / distance;
+        particle2.force.y += force_magnitude * dy / distance;
+        true
+    }
+
+    fn potential_energy(&self, particle1: &Particle, particle2: &Particle, offset: Vector2<f32>) -> f32 {
+        let dx = particle2.position.x + offset.x - particle1.position.x;
+        let dy = particle2.position.y + offset.y - particle1.position.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance >= self.cutoff {
+            return 0.0;
+        }
+        self.potential(distance) - self.potential(self.cutoff)
+    }
+
+    fn has_potential(&self) -> bool {
+        true
+    }
+
+    fn range(&self) -> Option<f32> {
+        Some(self.cutoff)
+    }
+}
+
+
+/// Several interaction laws acting at once, e.g. `ImpulseCollision` together with `CoulombLaw`.
+///
+/// Every pair is resolved by each law in `laws`, in order. The `fields` act on each particle on
+/// its own and are applied through `apply_fields`.
+///
+/// The world does not put a scenario's laws into a single composite: it builds one for the
+/// impulsive laws and one for the force laws (see `InteractionLaw::is_impulsive`), and resolves
+/// the impulsive one first. The order of the scenario's laws is kept only within each of them.
+pub struct CompositeLaw {
+    pub laws: Vec<Box<dyn InteractionLaw>>,
+    pub fields: Vec<Box<dyn ExternalField>>,
+}
+
+impl CompositeLaw {
+    pub fn new(laws: Vec<Box<dyn InteractionLaw>>, fields: Vec<Box<dyn ExternalField>>) -> CompositeLaw {
+        CompositeLaw { laws, fields }
+    }
+
+    /// Adds the force of every external field to `particle`.
+    pub fn apply_fields(&self, particle: &mut Particle) {
+        for field in &self.fields {
+            field.apply(particle);
+        }
+    }
+
+    /// Potential energy of `particle` in the external fields.
+    pub fn field_energy(&self, particle: &Particle) -> f32 {
+        self.fields.iter().map(|field| field.potential_energy(particle)).sum()
+    }
+}
+
+impl InteractionLaw for CompositeLaw {
+    /// Resolves every law for the pair, in order.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if any of the laws interacted, otherwise `false`.
+    fn resolve(&self, particle1: &mut Particle, particle2: &mut Particle) -> bool {
+        let mut interacted = false;
+        for law in &self.laws {
+            interacted |= law.resolve(particle1, particle2);
+        }
+        interacted
+    }
+
+    fn potential_energy(&self, particle1: &Particle, particle2: &Particle, offset: Vector2<f32>) -> f32 {
+        self.laws.iter().map(|law| law.potential_energy(particle1, particle2, offset)).sum()
+    }
+
+    fn has_potential(&self) -> bool {
+        self.laws.iter().any(|law| law.has_potential())
+    }
+
+    /// A composite is impulsive only if every one of its laws is.
+    fn is_impulsive(&self) -> bool {
+        !self.laws.is_empty() && self.laws.iter().all(|law| law.is_impulsive())
+    }
+
+    /// The longest range among the laws, or `None` if any of them is long-range.
+    fn range(&self) -> Option<f32> {
+        self.laws.iter().try_fold(0.0_f32, |acc, law| law.range().map(|range| acc.max(range)))
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum InteractionLawType {
     CoulombLaw,
-    ImpulseCollision
+    /// Hard-disc contacts with coefficient of restitution `restitution`: `1` for elastic
+    /// collisions, less for a granular gas that loses energy in every collision.
+    ImpulseCollision { restitution: f32 },
+    LennardJones
+}
+
+impl InteractionLawType {
+    /// Perfectly elastic hard-disc contacts.
+    pub fn elastic_collisions() -> InteractionLawType {
+        InteractionLawType::ImpulseCollision { restitution: 1.0 }
+    }
 }
 
 
+/// Creates the `CoulombLaw` used by the simulation.
+///
+/// Exposed separately from `build_interaction_law` because the Barnes–Hut evaluator needs the
+/// concrete law rather than a trait object.
+pub fn build_coulomb_law() -> CoulombLaw {
+    // For example, use a Coulomb constant and softening parameter.
+    CoulombLaw::new(8.987_552e9, 0.001, 2000.0)
+}
+
 /// Creates and returns an interaction law instance based on the given `InteractionLawType`.
 ///
 /// # Arguments
@@ -249,21 +531,115 @@ pub enum InteractionLawType {
 /// # Examples
 ///
 /// ```rust
+/// use rusticles::physics::laws::{build_interaction_law, InteractionLawType};
+///
 /// // Create a Coulomb law interaction.
 /// let law = build_interaction_law(InteractionLawType::CoulombLaw);
 ///
 /// // Create an impulse collision interaction.
-/// let impulse = build_interaction_law(InteractionLawType::ImpulseCollision);
+/// let impulse = build_interaction_law(InteractionLawType::ImpulseCollision { restitution: 1.0 });
 /// ```
 pub fn build_interaction_law(law_type: InteractionLawType) -> Box<dyn InteractionLaw> {
     match law_type {
-        InteractionLawType::CoulombLaw => {
-            // For example, use a Coulomb constant and softening parameter.
-            Box::new(CoulombLaw::new(8.9875517923e9, 0.001, 2000.0))
+        InteractionLawType::CoulombLaw => Box::new(build_coulomb_law()),
+        InteractionLawType::ImpulseCollision { restitution } => {
+            // For example, use correction_factor and penetration_slop.
+            Box::new(ImpulseCollision::new(restitution, 0.8, 0.01))
         }
-        InteractionLawType::ImpulseCollision => {
-            // For example, use restitution, correction_factor, and penetration_slop.
-            Box::new(ImpulseCollision::new(1.0, 0.8, 0.01))
+        InteractionLawType::LennardJones => {
+            // For example, use a well depth comparable to the particles' kinetic energy,
+            // sigma matching the default particle diameter and the usual 2.5 sigma cutoff.
+            Box::new(LennardJones::new(10000.0, 10.0, 25.0))
         }
     }
-}
\ No newline at end of file
+}
+/// Creates a `CompositeLaw` applying the given laws, in order, to every pair, and the given
+/// external fields, placed in the box `boundaries`, to every particle.
+pub fn build_composite_law(law_types: &[InteractionLawType], field_types: &[FieldType], boundaries: Vector4<f32>) -> CompositeLaw {
+    let laws = law_types.iter().map(|&law_type| build_interaction_law(law_type)).collect();
+    let fields = field_types.iter().map(|&field_type| build_external_field(field_type, boundaries)).collect();
+    CompositeLaw::new(laws, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disc(x: f32, y: f32, vx: f32, vy: f32, mass: f32) -> Particle {
+        Particle { mass, ..Particle::at(x, y, vx, vy) }
+    }
+
+    /// Checks that the pair force at each of `distances` is minus the derivative of the pair
+    /// potential, for two particles carrying `charge`.
+    fn assert_conservative(law: &dyn InteractionLaw, charge: f32, distances: &[f32]) {
+        let pair = |r: f32| {
+            (Particle { charge, ..Particle::at(100.0, 100.0, 0.0, 0.0) }, Particle { charge, ..Particle::at(100.0 + r, 100.0, 0.0, 0.0) })
+        };
+        let energy = |r: f32| {
+            let (p1, p2) = pair(r);
+            law.potential_energy(&p1, &p2, Vector2 { x: 0.0, y: 0.0 })
+        };
+        let h = 0.01;
+        for &r in distances {
+            let (mut p1, mut p2) = pair(r);
+            law.resolve(&mut p1, &mut p2);
+            // The force on the second particle points along +x when the pair repels.
+            let dvdr = (energy(r + h) - energy(r - h)) / (2.0 * h);
+            assert!((p2.force.x + dvdr).abs() < 1e-2 * (1.0 + p2.force.x.abs()), "r = {}: {} vs {}", r, p2.force.x, -dvdr);
+        }
+    }
+
+    #[test]
+    fn impulse_collision_conserves_momentum_and_applies_restitution() {
+        let law = ImpulseCollision::new(0.5, 0.8, 0.01);
+        let mut p1 = disc(100.0, 100.0, 120.0, 30.0, 1.0);
+        let mut p2 = disc(106.0, 105.0, -40.0, 10.0, 3.0);
+        let (nx, ny) = (6.0 / 61.0_f32.sqrt(), 5.0 / 61.0_f32.sqrt());
+        let normal_velocity = |p1: &Particle, p2: &Particle| (p2.velocity.x - p1.velocity.x) * nx + (p2.velocity.y - p1.velocity.y) * ny;
+        let momentum = |p1: &Particle, p2: &Particle| {
+            (p1.mass * p1.velocity.x + p2.mass * p2.velocity.x, p1.mass * p1.velocity.y + p2.mass * p2.velocity.y)
+        };
+        let before = (normal_velocity(&p1, &p2), momentum(&p1, &p2));
+
+        assert!(law.resolve(&mut p1, &mut p2));
+
+        let after = (normal_velocity(&p1, &p2), momentum(&p1, &p2));
+        assert!(before.0 < 0.0);
+        assert!((after.0 + 0.5 * before.0).abs() < 1e-3, "{} vs {}", after.0, -0.5 * before.0);
+        assert!((after.1.0 - before.1.0).abs() < 1e-3 && (after.1.1 - before.1.1).abs() < 1e-3);
+        // The heavier disc is pushed back less by the positional correction.
+        let shift1 = ((p1.position.x - 100.0).powi(2) + (p1.position.y - 100.0).powi(2)).sqrt();
+        let shift2 = ((p2.position.x - 106.0).powi(2) + (p2.position.y - 105.0).powi(2)).sqrt();
+        assert!((shift1 - 3.0 * shift2).abs() < 1e-3);
+    }
+    #[test]
+    fn built_impulse_collision_uses_the_configured_restitution() {
+        for restitution in [1.0, 0.9, 0.5] {
+            let law = build_interaction_law(InteractionLawType::ImpulseCollision { restitution });
+            let mut p1 = disc(100.0, 100.0, 50.0, 0.0, 1.0);
+            let mut p2 = disc(109.0, 100.0, -50.0, 0.0, 1.0);
+            assert!(law.resolve(&mut p1, &mut p2));
+            assert!((p2.velocity.x - p1.velocity.x - 100.0 * restitution).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn lennard_jones_force_is_minus_the_derivative_of_its_potential() {
+        // sigma = 10, so the force is capped below 8 and cut off at 25.
+        let law = LennardJones::new(10000.0, 10.0, 25.0);
+        assert_conservative(&law, 0.0, &[3.0, 6.0, 7.9, 8.1, 10.0, 11.2, 15.0, 24.0]);
+
+        let energy = |r: f32| law.potential_energy(&Particle::at(0.0, 0.0, 0.0, 0.0), &Particle::at(r, 0.0, 0.0, 0.0), Vector2 { x: 0.0, y: 0.0 });
+        assert_eq!(energy(25.0), 0.0);
+        assert!(energy(24.999).abs() < 0.1, "{}", energy(24.999));
+        // The well is 10000 deep at 2^(1/6) sigma, less the small shift that zeroes the cutoff.
+        assert!((energy(2.0_f32.powf(1.0 / 6.0) * 10.0) + 10000.0).abs() < 200.0);
+    }
+
+    #[test]
+    fn coulomb_force_is_minus_the_derivative_of_its_potential() {
+        // With radius 5 the force stops growing below a distance of sqrt(0.5).
+        let law = build_coulomb_law();
+        assert_conservative(&law, 0.001, &[0.3, 0.6, 1.0, 5.0, 50.0]);
+    }
+}
@@ -2,3 +2,18 @@ pub mod container;
 pub mod particles;
 pub mod utils;
 pub mod laws;
+pub mod world;
+pub mod broad_phase;
+pub mod barnes_hut;
+pub mod initial_conditions;
+pub mod integrators;
+pub mod event_driven;
+pub mod fields;
+pub mod scenario;
+pub mod thermostats;
+pub mod geometry;
+pub mod demon;
+pub mod partition;
+pub mod szilard;
+pub mod pressure;
+pub mod distribution;
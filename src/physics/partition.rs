@@ -0,0 +1,267 @@
+use std::collections::BTreeSet;
+use mint::Vector4;
+use crate::physics::demon::{
+    build_demon_policy, Decision, DemonLedger, DemonLimits, DemonPolicy, DemonPolicyType, DemonSchedule, Direction,
+    MeasurementModel,
+};
+use crate::physics::particles::Particle;
+use crate::physics::utils::{mod_f32, SimRng};
+
+
+/// An opening of finite height in a partition wall, guarded by its demon.
+///
+/// `centre` and `height` are fractions of the container height, so a door can be described
+/// before the size of the box is known.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Door {
+    pub centre: f32,
+    pub height: f32,
+    /// Whether the door currently stands open. While the demon is looking it closes the door
+    /// for every particle it turns back and opens it for every particle it lets through.
+    pub open: bool,
+}
+
+impl Door {
+    pub fn new(centre: f32, height: f32) -> Door {
+        Door { centre, height, open: true }
+    }
+
+    /// Top and bottom y coordinates of the opening inside `boundaries`.
+    pub fn span(&self, boundaries: Vector4<f32>) -> (f32, f32) {
+        let height = boundaries.z - boundaries.y;
+        let centre = boundaries.y + self.centre * height;
+        (centre - 0.5 * self.height * height, centre + 0.5 * self.height * height)
+    }
+}
+
+/// Description of a partition wall, used to build a `Partition` once the box is known.
+///
+/// Partitions are always vertical walls spanning the full height of the box, so a box with
+/// several of them is a row of chambers numbered from left to right. Slanted or curved walls
+/// belong to the fixed geometry instead, which has no demons.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PartitionSpec {
+    /// Horizontal position of the wall as a fraction of the container width.
+    pub x_fraction: f32,
+    /// The door in the wall; `None` lets the demon guard the whole height of the wall.
+    pub door: Option<Door>,
+    /// The demon's sorting rule.
+    pub policy: DemonPolicyType,
+    /// How accurately the demon measures the particles it sorts.
+    pub measurement: MeasurementModel,
+    /// How quickly and how often the demon can act.
+    pub limits: DemonLimits,
+}
+
+impl PartitionSpec {
+    /// A doorless wall in the middle of the box, guarded by an exact, unlimited threshold demon.
+    pub fn middle() -> PartitionSpec {
+        PartitionSpec {
+            x_fraction: 0.5,
+            door: None,
+            policy: DemonPolicyType::SpeedThreshold { fast_above: 140.0, slow_below: 10.0 },
+            measurement: MeasurementModel::exact(),
+            limits: DemonLimits::unlimited(),
+        }
+    }
+}
+
+/// A vertical wall at `x`, spanning the full height of the box, separating two neighbouring
+/// chambers, with its own demon.
+///
+/// The particle crossing it from the lower-numbered chamber on the left goes `LeftToRight`.
+pub struct Partition {
+    pub x: f32,
+    /// With a door, the wall is solid except for the door. Without one, the demon guards the
+    /// whole height of the wall.
+    pub door: Option<Door>,
+    /// The demon's sorting rule; `demon` is built from it.
+    pub policy: DemonPolicyType,
+    pub demon: Box<dyn DemonPolicy>,
+    pub measurement: MeasurementModel,
+    pub limits: DemonLimits,
+    schedule: DemonSchedule,
+    /// What crossed this wall and how many measurements its demon made.
+    pub ledger: DemonLedger,
+    /// Particles the demon has let through that are still touching the wall, so each passage
+    /// is measured once rather than on every step of it.
+    admitted: BTreeSet<usize>,
+    /// Particles the wall has sent back that are still touching it, so a particle resting
+    /// against a closed door is measured once rather than on every step it stays there.
+    turned_back: BTreeSet<usize>,
+}
+
+impl Partition {
+    /// Builds the partition described by `spec` inside `boundaries`.
+    pub fn new(spec: PartitionSpec, boundaries: Vector4<f32>) -> Partition {
+        Partition {
+            x: boundaries.x + spec.x_fraction * (boundaries.w - boundaries.x),
+            door: spec.door,
+            policy: spec.policy,
+            demon: build_demon_policy(spec.policy),
+            measurement: spec.measurement,
+            limits: spec.limits,
+            schedule: DemonSchedule::default(),
+            ledger: DemonLedger::default(),
+            admitted: BTreeSet::new(),
+            turned_back: BTreeSet::new(),
+        }
+    }
+
+    /// Replaces the demon's sorting rule, starting the new policy afresh.
+    pub fn set_policy(&mut self, policy_type: DemonPolicyType) {
+        self.policy = policy_type;
+        self.demon = build_demon_policy(policy_type);
+    }
+
+    /// Advances the demon's clock by one tick of length `dt`.
+    pub fn tick(&mut self, dt: f32) {
+        self.schedule.tick(&self.limits, dt);
+    }
+
+    /// Bounces particle `index` off the wall if it touches it and is not let through.
+    ///
+    /// A particle that was sent back keeps being sent back, without being measured again, until
+    /// it has left the wall. A particle the demon let through is only free to cross while it
+    /// stays within the door; should it drift onto the solid part of the wall on its way, it is
+    /// sent back after all.
+    pub fn collide(&mut self, index: usize, particle: &mut Particle, looking: bool, boundaries: Vector4<f32>, rng: &mut SimRng) {
+        if particle.radius < mod_f32(self.x - particle.position.x) {
+            self.admitted.remove(&index);
+            self.turned_back.remove(&index);
+        } else if self.admitted.contains(&index) {
+            if !self.in_door(particle, boundaries) {
+                self.admitted.remove(&index);
+                self.turned_back.insert(index);
+                self.send_back(particle);
+            }
+        } else if self.turned_back.contains(&index) || self.reflects(particle, looking, boundaries, rng) {
+            self.turned_back.insert(index);
+            self.send_back(particle);
+        } else {
+            self.admitted.insert(index);
+        }
+    }
+
+    /// Sends `particle` back into the chamber its centre is in.
+    fn send_back(&self, particle: &mut Particle) {
+        if particle.position.x < self.x {
+            particle.position.x = self.x - particle.radius;
+            particle.velocity.x = -mod_f32(particle.velocity.x);
+        } else {
+            particle.position.x = self.x + particle.radius;
+            particle.velocity.x = mod_f32(particle.velocity.x);
+        }
+    }
+
+    /// Whether `particle` is level with the door, or the wall has no door and is open along its
+    /// whole height.
+    fn in_door(&self, particle: &Particle, boundaries: Vector4<f32>) -> bool {
+        self.door.is_none_or(|door| {
+            let (top, bottom) = door.span(boundaries);
+            (top..=bottom).contains(&particle.position.y)
+        })
+    }
+
+    /// Decides whether the wall sends `particle` back when it reaches it.
+    ///
+    /// The solid part of a wall always does. At the door, or anywhere along a wall without a
+    /// door, the demon decides while it is `looking`, and the door is opened or closed to match.
+    pub fn reflects(&mut self, particle: &Particle, looking: bool, boundaries: Vector4<f32>, rng: &mut SimRng) -> bool {
+        if !self.in_door(particle, boundaries) {
+            return true;
+        }
+        let reflects = looking && self.demon_reflects(particle, rng);
+        if let Some(door) = self.door.as_mut() {
+            door.open = !reflects;
+        }
+        reflects
+    }
+
+    /// Decides whether the demon sends `particle` back, from its possibly imperfect measurement
+    /// of the particle and within the demon's `limits`.
+    fn demon_reflects(&mut self, particle: &Particle, rng: &mut SimRng) -> bool {
+        if !self.schedule.try_measure(&self.limits) {
+            return self.limits.busy_outcome == Decision::Reflect;
+        }
+        self.ledger.record_measurement();
+        let measured = self.measurement.measure(particle, rng);
+        let decision = self.demon.decide(&measured, Direction::of(particle));
+        let decision = self.measurement.corrupt(decision, rng);
+        self.schedule.submit(&self.limits, decision) == Decision::Reflect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mint::Vector2;
+    use rand::SeedableRng;
+    use crate::physics::container::TEST_BOUNDARIES;
+    use super::*;
+
+    #[test]
+    fn admitted_particles_cannot_slide_past_the_door() {
+        let boundaries = TEST_BOUNDARIES;
+        let mut partition = Partition::new(PartitionSpec { door: Some(Door::new(0.5, 0.2)), ..PartitionSpec::middle() }, boundaries);
+        let mut rng = SimRng::seed_from_u64(0);
+        let mut particle = Particle::at(497.0, 415.0, 100.0, 300.0);
+
+        // Let through at the lower edge of the door, which spans y = 280 to 420.
+        partition.collide(0, &mut particle, false, boundaries, &mut rng);
+        assert_eq!(particle.position.x, 497.0);
+
+        // Still straddling the wall, but now level with its solid part.
+        particle.position = Vector2 { x: 499.0, y: 425.0 };
+        partition.collide(0, &mut particle, false, boundaries, &mut rng);
+        assert_eq!(particle.position.x, 495.0);
+        assert_eq!(particle.velocity.x, -100.0);
+    }
+
+    #[test]
+    fn a_particle_held_against_a_closed_door_is_measured_once() {
+        let boundaries = TEST_BOUNDARIES;
+        let mut partition = Partition::new(PartitionSpec { door: Some(Door::new(0.5, 0.2)), ..PartitionSpec::middle() }, boundaries);
+        let mut rng = SimRng::seed_from_u64(0);
+        let mut particle = Particle::at(497.0, 350.0, 100.0, 0.0);
+
+        // Too fast to be let into the right chamber, and pushed back against the door each step.
+        for _ in 0..5 {
+            partition.collide(0, &mut particle, true, boundaries, &mut rng);
+            assert_eq!(particle.position.x, 495.0);
+            particle.velocity.x = 100.0;
+        }
+        assert_eq!(partition.ledger.measurements, 1);
+
+        // Once it has left the door, the next approach is measured again.
+        particle.position.x = 480.0;
+        partition.collide(0, &mut particle, true, boundaries, &mut rng);
+        particle.position.x = 497.0;
+        partition.collide(0, &mut particle, true, boundaries, &mut rng);
+        assert_eq!(partition.ledger.measurements, 2);
+    }
+
+    #[test]
+    fn decisions_arrive_after_the_latency_and_busy_arrivals_get_the_busy_outcome() {
+        let boundaries = TEST_BOUNDARIES;
+        let limits = DemonLimits { latency: 2, max_rate: Some(4.0), busy_outcome: Decision::Reflect };
+        let mut partition = Partition::new(PartitionSpec { limits, ..PartitionSpec::middle() }, boundaries);
+        let mut rng = SimRng::seed_from_u64(0);
+        // Heading into the right chamber, which only takes particles slower than 10.
+        let arrival = |speed: f32| Particle::at(497.0, 350.0, speed, 0.0);
+        let (fast, slow) = (arrival(100.0), arrival(5.0));
+
+        // The decision to turn the fast particle back is sent off, but the door is still open.
+        assert!(!partition.reflects(&fast, true, boundaries, &mut rng));
+        // The demon has used up its measurement, so the next arrival is turned back unmeasured.
+        assert!(partition.reflects(&fast, true, boundaries, &mut rng));
+        assert_eq!(partition.ledger.measurements, 1);
+
+        // One tick later the door still acts on the old decision...
+        partition.tick(0.25);
+        assert!(!partition.reflects(&slow, true, boundaries, &mut rng));
+        // ...and two ticks later on the first measurement.
+        partition.tick(0.25);
+        assert!(partition.reflects(&slow, true, boundaries, &mut rng));
+        assert_eq!(partition.ledger.measurements, 3);
+    }
+}
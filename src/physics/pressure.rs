@@ -0,0 +1,93 @@
+use std::collections::VecDeque;
+use crate::physics::container::Wall;
+
+
+/// Impulse delivered to the walls during one step.
+#[derive(Clone, Debug, Default)]
+struct Sample {
+    duration: f32,
+    /// Impulse on each outer wall, indexed by `Wall`.
+    walls: [f32; 4],
+    /// Impulse on the stretch of the outer walls around each chamber.
+    chambers: Vec<f32>,
+}
+
+/// Accumulates the momentum particles transfer to the outer walls over a sliding time window,
+/// from which `ParticleContainer` estimates the pressure on each wall and in each chamber.
+#[derive(Clone, Debug)]
+pub struct PressureGauge {
+    /// Length of the averaging window. A window of zero or less keeps no samples at all.
+    pub window: f32,
+    /// Impulse delivered since the last call to `sample`.
+    current: Sample,
+    /// Completed samples inside the window, oldest first.
+    samples: VecDeque<Sample>,
+}
+
+impl PressureGauge {
+    pub fn new(window: f32) -> PressureGauge {
+        PressureGauge { window, current: Sample::default(), samples: VecDeque::new() }
+    }
+
+    /// Books the `impulse` a particle in chamber `chamber` delivered to `wall`.
+    pub fn record(&mut self, wall: Wall, chamber: usize, impulse: f32) {
+        self.current.walls[wall as usize] += impulse;
+        if self.current.chambers.len() <= chamber {
+            self.current.chambers.resize(chamber + 1, 0.0);
+        }
+        self.current.chambers[chamber] += impulse;
+    }
+
+    /// Closes the current step of length `dt` and drops the samples that fell out of the window.
+    pub fn sample(&mut self, dt: f32) {
+        let mut sample = std::mem::take(&mut self.current);
+        sample.duration = dt;
+        self.samples.push_back(sample);
+        while !self.samples.is_empty() && self.duration() - self.samples[0].duration >= self.window {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Time covered by the samples in the window.
+    pub fn duration(&self) -> f32 {
+        self.samples.iter().map(|sample| sample.duration).sum()
+    }
+
+    /// Impulse delivered to `wall` within the window.
+    pub fn wall_impulse(&self, wall: Wall) -> f32 {
+        self.samples.iter().map(|sample| sample.walls[wall as usize]).sum()
+    }
+
+    /// Impulse delivered to the outer walls around chamber `chamber` within the window.
+    pub fn chamber_impulse(&self, chamber: usize) -> f32 {
+        self.samples.iter().map(|sample| sample.chambers.get(chamber).copied().unwrap_or(0.0)).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_keeps_recent_samples() {
+        let mut gauge = PressureGauge::new(1.0);
+        for _ in 0..10 {
+            gauge.record(Wall::Left, 0, 2.0);
+            gauge.sample(0.25);
+        }
+        assert_eq!(gauge.duration(), 1.0);
+        assert_eq!(gauge.wall_impulse(Wall::Left), 8.0);
+        assert_eq!(gauge.chamber_impulse(0), 8.0);
+    }
+
+    #[test]
+    fn empty_window_keeps_nothing() {
+        for window in [0.0, -1.0] {
+            let mut gauge = PressureGauge::new(window);
+            gauge.record(Wall::Ground, 1, 3.0);
+            gauge.sample(0.1);
+            assert_eq!(gauge.duration(), 0.0);
+            assert_eq!(gauge.wall_impulse(Wall::Ground), 0.0);
+        }
+    }
+}
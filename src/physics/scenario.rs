@@ -0,0 +1,338 @@
+use mint::Vector2;
+use crate::physics::container::{Wall, WallKind};
+use crate::physics::demon::{Decision, DemonLimits, DemonPolicyType, MeasurementModel};
+use crate::physics::fields::FieldType;
+use crate::physics::geometry::GeometryType;
+use crate::physics::initial_conditions::{ChargeDistribution, Tracers, VelocityDistribution};
+use crate::physics::integrators::IntegratorType;
+use crate::physics::laws::InteractionLawType;
+use crate::physics::partition::{Door, PartitionSpec};
+use crate::physics::szilard::{LoadType, SzilardSpec};
+use crate::physics::thermostats::{Region, ThermostatType};
+
+
+/// Everything needed to set up a run: how many particles, how they are charged and set moving,
+/// which laws act between them, which external fields and thermostats act on them and how they
+/// are integrated.
+///
+/// `laws` all act on every pair, so e.g. `[ImpulseCollision, CoulombLaw]` gives charged particles
+/// that also collide as hard discs. Impulsive laws are resolved first, once per step, and the
+/// force laws after them as often as the integrator asks; the order of `laws` only holds within
+/// each of the two groups.
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub num: u32,
+    pub laws: Vec<InteractionLawType>,
+    pub fields: Vec<FieldType>,
+    pub charges: ChargeDistribution,
+    pub velocities: VelocityDistribution,
+    /// Heavy tracer particles among the others, which otherwise all have unit mass.
+    pub tracers: Option<Tracers>,
+    pub integrator: IntegratorType,
+    pub thermostats: Vec<(Region, ThermostatType)>,
+    /// Kind of each outer wall, indexed by `Wall`.
+    pub walls: [WallKind; 4],
+    /// Whether the container wraps around horizontally and vertically.
+    pub periodic_x: bool,
+    pub periodic_y: bool,
+    /// Fixed geometry built inside the box.
+    pub geometry: GeometryType,
+    /// Vertical partition walls splitting the box into a row of chambers numbered from left to
+    /// right, each wall with its own demon.
+    pub partitions: Vec<PartitionSpec>,
+    /// A Szilard engine working in the box, if any.
+    pub szilard: Option<SzilardSpec>,
+    /// Seed of the simulation's random number generator.
+    pub seed: u64,
+}
+
+impl Scenario {
+    /// Neutral hard discs: the classic Maxwell's demon gas, in two chambers joined by the demon's door.
+    pub fn ideal_gas(num: u32) -> Scenario {
+        Scenario {
+            num,
+            laws: vec![InteractionLawType::elastic_collisions()],
+            fields: Vec::new(),
+            charges: ChargeDistribution::Neutral,
+            velocities: VelocityDistribution::Uniform { min: 50.0, max: 250.0 },
+            tracers: None,
+            integrator: IntegratorType::SemiImplicitEuler,
+            thermostats: Vec::new(),
+            walls: [WallKind::Specular; 4],
+            periodic_x: false,
+            periodic_y: false,
+            geometry: GeometryType::Box,
+            partitions: vec![demon_door()],
+            szilard: None,
+            seed: 0,
+        }
+    }
+
+    /// A neutral mix of positive and negative hard discs that also interact electrostatically.
+    ///
+    /// Unlike the ideal gas, the middle wall has no door: the demon guards its whole height, so
+    /// ions and their neighbours are not squeezed through a narrow opening. `plasma` inherits it.
+    pub fn charged_gas(num: u32) -> Scenario {
+        Scenario {
+            laws: vec![InteractionLawType::elastic_collisions(), InteractionLawType::CoulombLaw],
+            charges: ChargeDistribution::NeutralPlasma(0.001234),
+            integrator: IntegratorType::VelocityVerlet,
+            partitions: vec![PartitionSpec::middle()],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// A neutral plasma of point charges without contact forces.
+    pub fn plasma(num: u32) -> Scenario {
+        Scenario { laws: vec![InteractionLawType::CoulombLaw], ..Scenario::charged_gas(num) }
+    }
+
+    /// A Lennard-Jones fluid that can condense and phase-separate.
+    ///
+    /// The middle wall has no door, so liquid that condenses against it is not split by a solid
+    /// wall; the demon guards the wall's whole height instead.
+    pub fn lennard_jones_fluid(num: u32) -> Scenario {
+        Scenario {
+            laws: vec![InteractionLawType::LennardJones],
+            integrator: IntegratorType::VelocityVerlet,
+            partitions: vec![PartitionSpec::middle()],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// A granular gas: discs that lose a tenth of their approach speed in every collision, so
+    /// the gas cools down and clusters instead of settling to a steady temperature.
+    /// The event-driven engine (H) treats discs as elastic, so it does not cool this gas.
+    pub fn granular_gas(num: u32) -> Scenario {
+        Scenario {
+            laws: vec![InteractionLawType::ImpulseCollision { restitution: 0.9 }],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs under gravity, settling into a barometric density profile.
+    pub fn gravity(num: u32) -> Scenario {
+        Scenario {
+            fields: vec![FieldType::UniformGravity(Vector2 { x: 0.0, y: 30.0 })],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs all carrying the same charge, whose mutual repulsion pushes them out towards
+    /// the walls.
+    pub fn like_charges(num: u32) -> Scenario {
+        Scenario { charges: ChargeDistribution::Uniform(0.001234), ..Scenario::charged_gas(num) }
+    }
+
+    /// Hard discs with charges of either sign and any size up to that of the charged gas.
+    pub fn mixed_charges(num: u32) -> Scenario {
+        Scenario { charges: ChargeDistribution::Random { min: -0.001234, max: 0.001234 }, ..Scenario::charged_gas(num) }
+    }
+
+    /// The charged gas in a uniform electric field along the box, pulling the positive charges
+    /// to the right and the negative ones to the left.
+    pub fn electric_field(num: u32) -> Scenario {
+        Scenario {
+            fields: vec![FieldType::UniformElectricField(Vector2 { x: 10000.0, y: 0.0 })],
+            ..Scenario::charged_gas(num)
+        }
+    }
+
+    /// Hard discs held in a harmonic trap at the centre of the box, settling into a Gaussian cloud.
+    pub fn trap(num: u32) -> Scenario {
+        Scenario {
+            fields: vec![FieldType::HarmonicTrap { centre: Vector2 { x: 0.5, y: 0.5 }, stiffness: 0.3 }],
+            integrator: IntegratorType::VelocityVerlet,
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs held at a fixed temperature by a Nosé–Hoover thermostat (canonical ensemble),
+    /// starting from the Maxwell–Boltzmann distribution at that temperature.
+    pub fn canonical(num: u32) -> Scenario {
+        Scenario {
+            velocities: VelocityDistribution::MaxwellBoltzmann { temperature: 10000.0, remove_drift: true, exact_energy: true },
+            thermostats: vec![(Region::Whole, ThermostatType::NoseHoover { temperature: 10000.0, tau: 0.5 })],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs held at a fixed temperature by random collisions with a heat bath (Andersen
+    /// thermostat), which relaxes the speed distribution as well as the temperature.
+    pub fn heat_bath(num: u32) -> Scenario {
+        Scenario {
+            thermostats: vec![(Region::Whole, ThermostatType::Andersen { temperature: 10000.0, collision_rate: 0.5 })],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs with a few heavy tracers among them, which wander about in Brownian motion.
+    /// Velocities start from the Maxwell–Boltzmann distribution so the tracers start slow.
+    pub fn brownian(num: u32) -> Scenario {
+        Scenario {
+            velocities: VelocityDistribution::MaxwellBoltzmann { temperature: 10000.0, remove_drift: false, exact_energy: false },
+            tracers: Some(Tracers { count: 5, mass: 50.0, radius: 20.0 }),
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs with the left chamber held hot and the right chamber held cold.
+    pub fn two_baths(num: u32) -> Scenario {
+        Scenario {
+            thermostats: vec![
+                (Region::Chamber(0), ThermostatType::Berendsen { temperature: 20000.0, tau: 0.5 }),
+                (Region::Chamber(1), ThermostatType::Berendsen { temperature: 5000.0, tau: 0.5 }),
+            ],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs between a hot thermal wall on the left and a cold one on the right.
+    pub fn reservoirs(num: u32) -> Scenario {
+        let mut walls = [WallKind::Specular; 4];
+        walls[Wall::Left as usize] = WallKind::Thermal { temperature: 20000.0 };
+        walls[Wall::Right as usize] = WallKind::Thermal { temperature: 5000.0 };
+        Scenario { walls, ..Scenario::ideal_gas(num) }
+    }
+
+    /// Hard discs in a box whose walls re-emit a third of the particles that hit them at a cold
+    /// temperature and reflect the rest, so the gas cools more slowly than between thermal walls.
+    pub fn rough_walls(num: u32) -> Scenario {
+        Scenario {
+            walls: [WallKind::Diffuse { accommodation: 0.3, temperature: 5000.0 }; 4],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs in a box that is periodic along both axes, for bulk properties free of wall effects.
+    ///
+    /// The middle partition stays, but without a door, so apart from the demon's sorting the gas
+    /// meets no solid wall anywhere.
+    pub fn bulk(num: u32) -> Scenario {
+        Scenario {
+            periodic_x: true,
+            periodic_y: true,
+            partitions: vec![PartitionSpec::middle()],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// Hard discs in a round container.
+    pub fn circle(num: u32) -> Scenario {
+        Scenario { geometry: GeometryType::Circle, ..Scenario::ideal_gas(num) }
+    }
+
+    /// Hard discs in two triangular chambers joined by a narrow neck at the demon's wall.
+    pub fn hourglass(num: u32) -> Scenario {
+        Scenario { geometry: GeometryType::Hourglass { neck: 120.0 }, ..Scenario::ideal_gas(num) }
+    }
+
+    /// Hard discs with the left chamber funnelling into a narrow opening at the demon's wall.
+    pub fn funnel(num: u32) -> Scenario {
+        Scenario { geometry: GeometryType::Funnel { opening: 120.0 }, ..Scenario::ideal_gas(num) }
+    }
+
+    /// Hard discs scattering off fixed discs and polygons.
+    pub fn obstacles(num: u32) -> Scenario {
+        Scenario { geometry: GeometryType::Obstacles, ..Scenario::ideal_gas(num) }
+    }
+
+    /// The classic demon gas with a demon that measures speeds with noise, in coarse bins, and
+    /// sometimes gets its decision wrong.
+    pub fn noisy_demon(num: u32) -> Scenario {
+        Scenario {
+            partitions: vec![PartitionSpec {
+                measurement: MeasurementModel { speed_noise: 40.0, misclassification: 0.05, resolution: 20.0 },
+                ..demon_door()
+            }],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// The classic demon gas with a demon that acts a few steps late and can only measure a few
+    /// particles per second, keeping the door shut while it is busy.
+    pub fn slow_demon(num: u32) -> Scenario {
+        Scenario {
+            partitions: vec![PartitionSpec {
+                limits: DemonLimits { latency: 3, max_rate: Some(5.0), busy_outcome: Decision::Reflect },
+                ..demon_door()
+            }],
+            ..Scenario::ideal_gas(num)
+        }
+    }
+
+    /// A three-stage sorter: four chambers in a row, each wall with a median-speed demon pushing
+    /// fast particles one chamber further to the left.
+    pub fn cascade(num: u32) -> Scenario {
+        let stage = |x_fraction: f32| PartitionSpec {
+            x_fraction,
+            policy: DemonPolicyType::MedianSpeed { tau: 1.0 },
+            ..demon_door()
+        };
+        Scenario { partitions: vec![stage(0.25), stage(0.5), stage(0.75)], ..Scenario::ideal_gas(num) }
+    }
+
+    /// Szilard's engine: a single particle in a box whose thermal walls are a heat bath, and a
+    /// piston lifting a load that tracks the particle's pressure. `num` is ignored.
+    pub fn szilard(_num: u32) -> Scenario {
+        Scenario {
+            walls: [WallKind::Thermal { temperature: 30000.0 }; 4],
+            velocities: VelocityDistribution::MaxwellBoltzmann { temperature: 30000.0, remove_drift: false, exact_energy: false },
+            partitions: Vec::new(),
+            szilard: Some(SzilardSpec {
+                load: LoadType::Isothermal { fraction: 0.9 },
+                piston_mass: 5.0,
+                stroke_time: 600.0,
+            }),
+            ..Scenario::ideal_gas(1)
+        }
+    }
+
+    /// Szilard's engine lifting a constant weight, which can extract at most `kT / 2` per cycle
+    /// however the weight is chosen. `num` is ignored.
+    pub fn szilard_weight(num: u32) -> Scenario {
+        let engine = Scenario::szilard(num);
+        Scenario {
+            szilard: engine.szilard.map(|spec| SzilardSpec { load: LoadType::Constant { force: 12.0 }, ..spec }),
+            ..engine
+        }
+    }
+
+    /// Looks up a preset by its command-line name.
+    pub fn by_name(name: &str, num: u32) -> Option<Scenario> {
+        match name {
+            "ideal-gas" => Some(Scenario::ideal_gas(num)),
+            "charged-gas" => Some(Scenario::charged_gas(num)),
+            "plasma" => Some(Scenario::plasma(num)),
+            "like-charges" => Some(Scenario::like_charges(num)),
+            "mixed-charges" => Some(Scenario::mixed_charges(num)),
+            "lennard-jones" => Some(Scenario::lennard_jones_fluid(num)),
+            "granular-gas" => Some(Scenario::granular_gas(num)),
+            "gravity" => Some(Scenario::gravity(num)),
+            "electric-field" => Some(Scenario::electric_field(num)),
+            "trap" => Some(Scenario::trap(num)),
+            "canonical" => Some(Scenario::canonical(num)),
+            "heat-bath" => Some(Scenario::heat_bath(num)),
+            "brownian" => Some(Scenario::brownian(num)),
+            "two-baths" => Some(Scenario::two_baths(num)),
+            "reservoirs" => Some(Scenario::reservoirs(num)),
+            "rough-walls" => Some(Scenario::rough_walls(num)),
+            "bulk" => Some(Scenario::bulk(num)),
+            "circle" => Some(Scenario::circle(num)),
+            "hourglass" => Some(Scenario::hourglass(num)),
+            "funnel" => Some(Scenario::funnel(num)),
+            "obstacles" => Some(Scenario::obstacles(num)),
+            "noisy-demon" => Some(Scenario::noisy_demon(num)),
+            "slow-demon" => Some(Scenario::slow_demon(num)),
+            "cascade" => Some(Scenario::cascade(num)),
+            "szilard" => Some(Scenario::szilard(num)),
+            "szilard-weight" => Some(Scenario::szilard_weight(num)),
+            _ => None,
+        }
+    }
+}
+
+/// The middle wall with the demon's door: a fifth of the box high, halfway down.
+fn demon_door() -> PartitionSpec {
+    PartitionSpec { door: Some(Door::new(0.5, 0.2)), ..PartitionSpec::middle() }
+}
@@ -0,0 +1,230 @@
+use mint::Vector4;
+use crate::physics::particles::Particle;
+use crate::physics::utils::mod_f32;
+
+
+/// Narrowest chamber, as a fraction of the box width, the isothermal load is sized for. An
+/// expansion never drops below half the box, so this only caps the force while the piston
+/// strays into the particle's chamber, where `kT / w` would otherwise grow without bound.
+const MIN_LOAD_WIDTH: f32 = 0.25;
+
+/// The load the piston of a Szilard engine works against.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LoadType {
+    /// A constant force, like a weight hanging from the piston.
+    Constant { force: f32 },
+    /// A force that follows `fraction` of the mean force `kT / w` a single particle exerts on the
+    /// piston from a chamber of width `w`, capped at a quarter of the box. The work done on it
+    /// only depends on where the piston starts and stops, so a stroke that reaches the far wall
+    /// does `fraction kT ln 2`, and as `fraction` approaches 1 the work per cycle approaches
+    /// `kT ln 2`.
+    Isothermal { fraction: f32 },
+}
+
+/// Description of a Szilard engine, used to build a `SzilardEngine`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SzilardSpec {
+    pub load: LoadType,
+    pub piston_mass: f32,
+    /// Longest time an expansion stroke may take before the cycle is ended.
+    pub stroke_time: f32,
+}
+
+/// The partition of a Szilard engine once it has been inserted, acting as a piston.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Piston {
+    pub x: f32,
+    pub velocity: f32,
+    /// Direction of the load force on the piston: `-1` when the demon found the particle on the
+    /// left, `1` when it found it on the right, and `0` when nothing is hooked up.
+    pub load_direction: f32,
+    /// Work done on the load so far in this stroke.
+    pub work: f32,
+    /// Time since the piston was inserted.
+    pub elapsed: f32,
+}
+
+/// Szilard's one-particle engine.
+///
+/// Each cycle a partition is inserted in the middle of the box as soon as no particle touches
+/// it. While the demon is looking it measures which side the particle is on, which costs one
+/// bit, and hooks the load up so that the expansion of that side lifts it. The partition then
+/// slides as a piston of mass `piston_mass`, pushed by the particle and held back by the load,
+/// until it reaches the wall on the far side from the particle or `stroke_time` runs out, and is
+/// taken out again. The kinetic energy the piston still carries then is lost to the walls, and
+/// is booked as dissipated rather than as work. Without the measurement the load is left
+/// unhooked and the particle expands freely, doing no work.
+///
+/// The walls should be thermal so that the particle draws the work from a heat bath. On
+/// average no cycle can then extract more than `kT ln 2`, the Landauer cost of the bit.
+pub struct SzilardEngine {
+    pub load: LoadType,
+    pub piston_mass: f32,
+    pub stroke_time: f32,
+    /// The piston, while it is in the box.
+    pub piston: Option<Piston>,
+    /// Number of completed cycles.
+    pub cycles: u32,
+    /// Number of measurements the demon has made.
+    pub measurements: u32,
+    /// Work done on the load over all completed cycles.
+    pub total_work: f32,
+    /// Work done on the load in the last completed cycle.
+    pub last_work: f32,
+    /// Kinetic energy the piston lost against the walls or when it was taken out, summed over
+    /// all cycles. Together with `total_work` it is the energy the particle gave the piston.
+    pub dissipated: f32,
+    /// Time elapsed and kinetic energy per particle integrated over it, for the average `kT`.
+    time: f32,
+    energy_time: f32,
+}
+
+impl SzilardEngine {
+    pub fn new(spec: SzilardSpec) -> SzilardEngine {
+        SzilardEngine {
+            load: spec.load,
+            piston_mass: spec.piston_mass,
+            stroke_time: spec.stroke_time,
+            piston: None,
+            cycles: 0,
+            measurements: 0,
+            total_work: 0.0,
+            last_work: 0.0,
+            dissipated: 0.0,
+            time: 0.0,
+            energy_time: 0.0,
+        }
+    }
+
+    /// Time average of the kinetic energy per particle, i.e. `kT` of the bath.
+    pub fn kt(&self) -> f32 {
+        if self.time > 0.0 { self.energy_time / self.time } else { 0.0 }
+    }
+
+    /// The most work a cycle can extract on average, `kT ln 2`.
+    pub fn landauer_work(&self) -> f32 {
+        self.kt() * std::f32::consts::LN_2
+    }
+
+    /// Landauer cost of erasing the bits the demon has measured, at the bath's average `kT`.
+    pub fn landauer_cost(&self) -> f32 {
+        self.measurements as f32 * self.landauer_work()
+    }
+
+    /// Average work done on the load per completed cycle.
+    pub fn work_per_cycle(&self) -> f32 {
+        if self.cycles > 0 { self.total_work / self.cycles as f32 } else { 0.0 }
+    }
+
+    /// Size of the load force on `piston` inside `boundaries`.
+    fn load_force(&self, piston: &Piston, boundaries: Vector4<f32>) -> f32 {
+        match self.load {
+            LoadType::Constant { force } => force,
+            LoadType::Isothermal { fraction } => {
+                // Width of the chamber the particle was found in.
+                let width = if piston.load_direction < 0.0 { piston.x - boundaries.x } else { boundaries.w - piston.x };
+                fraction * self.kt() / width.max(MIN_LOAD_WIDTH * (boundaries.w - boundaries.x))
+            }
+        }
+    }
+
+    /// Runs the cycle for a step of length `dt`: inserts the partition and measures, or moves
+    /// the piston under the load and ends the stroke when it is over.
+    pub fn advance(&mut self, particles: &[Particle], looking: bool, boundaries: Vector4<f32>, dt: f32) {
+        if particles.is_empty() {
+            return;
+        }
+        let kinetic_energy: f32 = particles.iter().map(|particle| particle.kinetic_energy()).sum();
+        self.time += dt;
+        self.energy_time += kinetic_energy / particles.len() as f32 * dt;
+
+        let Some(mut piston) = self.piston else {
+            let middle = 0.5 * (boundaries.x + boundaries.w);
+            if particles.iter().all(|particle| mod_f32(particle.position.x - middle) > particle.radius) {
+                let load_direction = if looking {
+                    self.measurements += 1;
+                    let left = particles.iter().filter(|particle| particle.position.x < middle).count();
+                    if 2 * left >= particles.len() { -1.0 } else { 1.0 }
+                } else {
+                    0.0
+                };
+                self.piston = Some(Piston { x: middle, velocity: 0.0, load_direction, work: 0.0, elapsed: 0.0 });
+            }
+            return;
+        };
+
+        let force = self.load_force(&piston, boundaries);
+        piston.velocity += piston.load_direction * force / self.piston_mass * dt;
+        let previous = piston.x;
+        piston.x = (piston.x + piston.velocity * dt).clamp(boundaries.x, boundaries.w);
+        piston.work -= piston.load_direction * force * (piston.x - previous);
+        piston.elapsed += dt;
+
+        // The stroke is over once the particle's chamber fills the box. A piston driven back
+        // against the other wall by the load stops there, losing its kinetic energy.
+        let at_left = piston.x <= boundaries.x;
+        let at_right = piston.x >= boundaries.w;
+        let expanded = if piston.load_direction < 0.0 {
+            at_right
+        } else if piston.load_direction > 0.0 {
+            at_left
+        } else {
+            at_left || at_right
+        };
+        let kinetic_energy = 0.5 * self.piston_mass * piston.velocity * piston.velocity;
+
+        if expanded || piston.elapsed >= self.stroke_time {
+            self.cycles += 1;
+            self.last_work = piston.work;
+            self.total_work += piston.work;
+            self.dissipated += kinetic_energy;
+            self.piston = None;
+        } else {
+            if at_left || at_right {
+                self.dissipated += kinetic_energy;
+                piston.velocity = 0.0;
+            }
+            self.piston = Some(piston);
+        }
+    }
+
+    /// Bounces `particle` elastically off the piston if it touches it.
+    pub fn collide(&mut self, particle: &mut Particle) {
+        let Some(piston) = self.piston.as_mut() else {
+            return;
+        };
+        if particle.radius < mod_f32(piston.x - particle.position.x) {
+            return;
+        }
+        // Keep the particle on the side its centre is on.
+        let side = if particle.position.x < piston.x { -1.0 } else { 1.0 };
+        particle.position.x = piston.x + side * particle.radius;
+        if (particle.velocity.x - piston.velocity) * side < 0.0 {
+            let (m, big_m) = (particle.mass, self.piston_mass);
+            let (v, u) = (particle.velocity.x, piston.velocity);
+            particle.velocity.x = ((m - big_m) * v + 2.0 * big_m * u) / (m + big_m);
+            piston.velocity = ((big_m - m) * u + 2.0 * m * v) / (m + big_m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::scenario::Scenario;
+    use crate::physics::world::World;
+    use super::*;
+
+    #[test]
+    fn quasi_static_cycles_extract_close_to_kt_ln_2() {
+        let spec = SzilardSpec { load: LoadType::Isothermal { fraction: 0.95 }, piston_mass: 20.0, stroke_time: 200.0 };
+        let mut world = World::new(200.0, 200.0, Scenario { szilard: Some(spec), seed: 1, ..Scenario::szilard(1) });
+        world.container.demon_looking = true;
+        for _ in 0..300 * 60 {
+            world.step(1.0 / 60.0);
+        }
+        let engine = world.container.szilard.as_ref().unwrap();
+        assert!(engine.cycles >= 10);
+        assert!(engine.work_per_cycle() > 0.0);
+        assert!((engine.work_per_cycle() / engine.landauer_work() - 1.0).abs() < 0.15);
+    }
+}
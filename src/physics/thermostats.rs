@@ -0,0 +1,186 @@
+use rand::Rng;
+use crate::physics::container::ParticleContainer;
+use crate::physics::particles::Particle;
+use crate::physics::utils::{random_gaussian, SimRng};
+
+
+/// Kinetic temperature of the given particles.
+///
+/// In 2D equipartition gives `<KE> = kT` per particle, with `k = 1` in simulation units.
+pub fn temperature(particles: &[Particle], selected: &[usize]) -> f32 {
+    if selected.is_empty() {
+        return 0.0;
+    }
+    let total: f32 = selected.iter().map(|&i| particles[i].kinetic_energy()).sum();
+    total / selected.len() as f32
+}
+
+/// Which particles a thermostat acts on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// Every particle in the box.
+    Whole,
+    /// Only the particles currently in the chamber with this index (see `ParticleContainer::chamber_of`).
+    Chamber(usize),
+}
+
+impl Region {
+    /// Indices of the particles currently inside the region.
+    pub fn select(&self, particles: &[Particle], container: &ParticleContainer) -> Vec<usize> {
+        (0..particles.len())
+            .filter(|&i| match self {
+                Region::Whole => true,
+                Region::Chamber(chamber) => container.chamber_of(&particles[i]) == *chamber,
+            })
+            .collect()
+    }
+}
+
+/// Couples a set of particles to a heat bath at a target temperature.
+///
+/// Thermostats act on velocities after each step, and may keep state between steps.
+pub trait Thermostat {
+    /// Adjusts the velocities of the `selected` particles after a step of length `dt`, drawing
+    /// any random numbers from `rng`.
+    fn apply(&mut self, particles: &mut [Particle], selected: &[usize], dt: f32, rng: &mut SimRng);
+}
+
+/// Berendsen weak coupling: rescales velocities so the temperature relaxes exponentially
+/// towards the target with time constant `tau`. Fast and robust, but does not sample the
+/// canonical ensemble exactly.
+pub struct Berendsen {
+    pub temperature: f32,
+    pub tau: f32,
+}
+
+impl Thermostat for Berendsen {
+    fn apply(&mut self, particles: &mut [Particle], selected: &[usize], dt: f32, _rng: &mut SimRng) {
+        let current = temperature(particles, selected);
+        if current <= 0.0 {
+            return;
+        }
+        let lambda = (1.0 + dt / self.tau * (self.temperature / current - 1.0)).max(0.0).sqrt();
+        for &i in selected {
+            particles[i].velocity.x *= lambda;
+            particles[i].velocity.y *= lambda;
+        }
+    }
+}
+
+/// Andersen stochastic collisions: each particle is hit by a bath particle with probability
+/// `collision_rate * dt` per step and gets a fresh Maxwell–Boltzmann velocity.
+pub struct Andersen {
+    pub temperature: f32,
+    pub collision_rate: f32,
+}
+
+impl Thermostat for Andersen {
+    fn apply(&mut self, particles: &mut [Particle], selected: &[usize], dt: f32, rng: &mut SimRng) {
+        let probability = (self.collision_rate * dt).min(1.0);
+        for &i in selected {
+            if rng.gen_range(0.0..1.0) < probability {
+                // Each velocity component is normal with variance kT / m.
+                let sigma = (self.temperature * particles[i].inverse_mass()).sqrt();
+                particles[i].velocity.x = sigma * random_gaussian(rng);
+                particles[i].velocity.y = sigma * random_gaussian(rng);
+            }
+        }
+    }
+}
+
+/// Nosé–Hoover: a friction coefficient `xi` with its own dynamics,
+/// `dxi/dt = (T / T0 - 1) / tau²`, slows or speeds up the particles. Deterministic and samples
+/// the canonical ensemble for ergodic systems.
+pub struct NoseHoover {
+    pub temperature: f32,
+    pub tau: f32,
+    /// Current value of the thermostat friction.
+    pub xi: f32,
+}
+
+impl Thermostat for NoseHoover {
+    fn apply(&mut self, particles: &mut [Particle], selected: &[usize], dt: f32, _rng: &mut SimRng) {
+        let current = temperature(particles, selected);
+        self.xi += dt / (self.tau * self.tau) * (current / self.temperature - 1.0);
+        let scale = (-self.xi * dt).exp();
+        for &i in selected {
+            particles[i].velocity.x *= scale;
+            particles[i].velocity.y *= scale;
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ThermostatType {
+    Berendsen { temperature: f32, tau: f32 },
+    Andersen { temperature: f32, collision_rate: f32 },
+    NoseHoover { temperature: f32, tau: f32 },
+}
+
+/// Creates and returns a thermostat instance based on the given `ThermostatType`.
+pub fn build_thermostat(thermostat_type: ThermostatType) -> Box<dyn Thermostat> {
+    match thermostat_type {
+        ThermostatType::Berendsen { temperature, tau } => Box::new(Berendsen { temperature, tau }),
+        ThermostatType::Andersen { temperature, collision_rate } => Box::new(Andersen { temperature, collision_rate }),
+        ThermostatType::NoseHoover { temperature, tau } => Box::new(NoseHoover { temperature, tau, xi: 0.0 }),
+    }
+}
+
+/// A thermostat together with the region it acts on.
+pub struct RegionThermostat {
+    pub region: Region,
+    pub thermostat: Box<dyn Thermostat>,
+}
+
+impl RegionThermostat {
+    pub fn new(region: Region, thermostat_type: ThermostatType) -> RegionThermostat {
+        RegionThermostat { region, thermostat: build_thermostat(thermostat_type) }
+    }
+
+    /// Applies the thermostat to the particles currently inside its region.
+    pub fn apply(&mut self, particles: &mut [Particle], container: &ParticleContainer, dt: f32, rng: &mut SimRng) {
+        let selected = self.region.select(particles, container);
+        self.thermostat.apply(particles, &selected, dt, rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::physics::container::TEST_BOUNDARIES;
+    use super::*;
+
+    /// Applies the thermostat to non-interacting particles for `num_steps` steps and returns the
+    /// temperature after every step.
+    fn run(thermostat_type: ThermostatType, num_steps: usize) -> Vec<f32> {
+        let mut rng = SimRng::seed_from_u64(3);
+        let container = ParticleContainer::new(TEST_BOUNDARIES);
+        let mut particles: Vec<Particle> = (0..1000).map(|_| Particle::new(&container, &mut rng)).collect();
+        let selected: Vec<usize> = (0..particles.len()).collect();
+        let mut thermostat = build_thermostat(thermostat_type);
+        (0..num_steps)
+            .map(|_| {
+                thermostat.apply(&mut particles, &selected, 1.0 / 60.0, &mut rng);
+                temperature(&particles, &selected)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn thermostats_bring_the_temperature_to_the_target() {
+        for target in [2000.0, 40000.0] {
+            // Berendsen and Andersen settle within ten coupling times.
+            let berendsen = run(ThermostatType::Berendsen { temperature: target, tau: 0.5 }, 300);
+            let andersen = run(ThermostatType::Andersen { temperature: target, collision_rate: 2.0 }, 300);
+            for temperatures in [berendsen, andersen] {
+                let last = *temperatures.last().unwrap();
+                assert!((last / target - 1.0).abs() < 0.05, "{} vs {}", last, target);
+            }
+            // Without collisions Nosé–Hoover keeps oscillating about the target, which its
+            // time average then matches.
+            let nose_hoover = run(ThermostatType::NoseHoover { temperature: target, tau: 0.5 }, 6000);
+            let average = nose_hoover.iter().sum::<f32>() / nose_hoover.len() as f32;
+            assert!((average / target - 1.0).abs() < 0.05, "{} vs {}", average, target);
+        }
+    }
+}
@@ -1,20 +1,33 @@
-use ggez::mint::Vector2;
+use mint::Vector2;
 use rand::Rng;
+use rand_chacha::ChaCha8Rng;
 
 // Structure representing a single particle.
 pub fn mod_f32(x: f32) -> f32 {
     if x < 0.0 {
-        return -x
+        -x
     } else {
-        return x
+        x
     }
 }
 
-pub fn random_vector(max: f32, min: f32) -> Vector2<f32> {
-    let mut rng = rand::thread_rng();
+/// The simulation's random number generator. It is seeded, so that a run can be repeated
+/// exactly, and a single one is threaded through everything random in `World`. A named
+/// algorithm is used rather than `StdRng`, whose stream may change between versions of `rand`.
+pub type SimRng = ChaCha8Rng;
+
+pub fn random_vector<R: Rng>(max: f32, min: f32, rng: &mut R) -> Vector2<f32> {
     let speed = rng.gen_range(min..max);
     let angle = rng.gen_range(0.0..(2.0 * std::f32::consts::PI));
     let vx = angle.cos() * speed;
     let vy = angle.sin() * speed;
-    return Vector2 { x: vx, y: vy };
+    Vector2 { x: vx, y: vy }
+}
+
+/// Draws a sample from the standard normal distribution using the Box–Muller transform.
+pub fn random_gaussian<R: Rng>(rng: &mut R) -> f32 {
+    // Keep u1 away from zero so the logarithm stays finite.
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen_range(0.0..1.0);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
 }
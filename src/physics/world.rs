@@ -0,0 +1,725 @@
+use mint::{Vector2, Vector4};
+use rand::SeedableRng;
+use crate::physics::barnes_hut::BarnesHut;
+use crate::physics::broad_phase::{resolve_all_pairs, SpatialGrid};
+use crate::physics::container::ParticleContainer;
+use crate::physics::demon::{DemonPolicyType, Direction};
+use crate::physics::distribution::EnergyHistogram;
+use crate::physics::event_driven::EventDriven;
+use crate::physics::fields::{build_external_field, FieldType};
+use crate::physics::geometry::{build_geometry, is_open};
+use crate::physics::integrators::{build_integrator, IntegratorType};
+use crate::physics::initial_conditions::{assign_charges, assign_velocities};
+use crate::physics::laws::{build_composite_law, build_coulomb_law, build_interaction_law, CompositeLaw, InteractionLaw, InteractionLawType};
+use crate::physics::particles::Particle;
+use crate::physics::partition::Partition;
+use crate::physics::scenario::Scenario;
+use crate::physics::szilard::SzilardEngine;
+use crate::physics::thermostats::{temperature, Region, RegionThermostat};
+use crate::physics::utils::SimRng;
+
+
+/// Number of overlapping positions drawn in a row before a new particle may overlap anyway.
+const MAX_PLACEMENT_MISSES: u32 = 1000;
+
+/// The headless simulation: the particles, the box they live in and the laws they obey.
+///
+/// `World` has no knowledge of windows or frame timers. It is advanced with an explicit `dt`,
+/// so it can be driven by the renderer, a batch job or a test on a machine without a display.
+/// Everything random is drawn from one generator seeded by `Scenario::seed`, so the same seed
+/// and the same sequence of `dt` give bit-identical trajectories.
+pub struct World {
+    pub particles: Vec<Particle>,
+    pub container: ParticleContainer,
+    /// Laws applied, in order, to every pair of particles.
+    pub laws: Vec<InteractionLawType>,
+    /// External fields acting on every particle.
+    pub fields: Vec<FieldType>,
+    pub integrator: IntegratorType,
+    /// Number of sub-steps each call to `step` is split into.
+    pub substeps: u32,
+    /// Use the spatial-grid broad phase for finite-range laws instead of testing every pair.
+    pub broad_phase: bool,
+    /// Barnes–Hut evaluator replacing the pair loop for `CoulombLaw`. It does not see periodic
+    /// images, so periodic containers keep the pair loop even when it is set.
+    pub barnes_hut: Option<BarnesHut>,
+    /// When set, particles are advanced as hard discs from event to event instead of time-stepped.
+    pub event_driven: Option<EventDriven>,
+    /// Thermostats applied after every step, each to its own region.
+    pub thermostats: Vec<RegionThermostat>,
+    /// Number of steps between updates of `distributions`.
+    pub distribution_interval: u32,
+    /// Kinetic-energy histogram of each chamber, updated every `distribution_interval` steps.
+    pub distributions: Vec<EnergyHistogram>,
+    /// Number of steps between updates of `energy`.
+    pub energy_interval: u32,
+    /// Total energy, updated every `energy_interval` steps, as summing the pair energy of a
+    /// long-range law visits every pair.
+    pub energy: f32,
+    /// Number of calls to `step` so far.
+    steps: u64,
+    /// Seed the random number generator was started from.
+    pub seed: u64,
+    rng: SimRng,
+    /// Chamber each particle was in when transfers were last booked.
+    chambers: Vec<usize>,
+    /// Horizontal position of each particle when transfers were last booked.
+    previous_x: Vec<f32>,
+    /// Positions `Particle::force` was last evaluated at, while it is still valid for them.
+    force_positions: Vec<Vector2<f32>>,
+    /// Entropy of each chamber when the world was created.
+    initial_entropy: Vec<f32>,
+}
+
+impl World {
+    /// Creates a world inside a `width` x `height` box, populated as described by `scenario`.
+    pub fn new(width: f32, height: f32, scenario: Scenario) -> World {
+        let mut container = ParticleContainer::new(Vector4 {
+            x: 0.0,
+            y: 0.0,
+            z: height,
+            w: width,
+        });
+        container.walls = scenario.walls;
+        container.periodic_x = scenario.periodic_x;
+        container.periodic_y = scenario.periodic_y;
+        container.shapes = build_geometry(scenario.geometry, container.boundaries);
+        let mut partitions: Vec<Partition> = scenario.partitions
+            .iter()
+            .map(|&spec| Partition::new(spec, container.boundaries))
+            .collect();
+        partitions.sort_by(|a, b| a.x.total_cmp(&b.x));
+        container.partitions = partitions;
+        container.szilard = scenario.szilard.map(SzilardEngine::new);
+
+        // Particles are placed in the region around the centre of the box, clear of the geometry
+        // and of each other, so that no pair starts deep inside a repulsive core. Should the box
+        // be too crowded for that, overlaps are let through after enough misses in a row.
+        let centre = Vector2 { x: 0.5 * width, y: 0.5 * height };
+        let mut rng = SimRng::seed_from_u64(scenario.seed);
+        let mut particles: Vec<Particle> = Vec::new();
+        let mut misses = 0;
+        while particles.len() < scenario.num as usize {
+            let mut particle = Particle::new(&container, &mut rng);
+            if let Some(tracers) = scenario.tracers.filter(|tracers| particles.len() < tracers.count as usize) {
+                particle.mass = tracers.mass;
+                particle.radius = tracers.radius;
+            }
+            let clear = misses >= MAX_PLACEMENT_MISSES || particles.iter().all(|other| !overlaps(&container, other, &particle));
+            if is_open(&container.shapes, centre, &particle) && clear {
+                particles.push(particle);
+                misses = 0;
+            } else {
+                misses += 1;
+            }
+        }
+        assign_charges(&mut particles, scenario.charges, &mut rng);
+        assign_velocities(&mut particles, scenario.velocities, &mut rng);
+        let mut world = World {
+            particles,
+            container,
+            laws: scenario.laws,
+            fields: scenario.fields,
+            integrator: scenario.integrator,
+            substeps: 20,
+            broad_phase: true,
+            barnes_hut: None,
+            event_driven: None,
+            thermostats: scenario.thermostats
+                .into_iter()
+                .map(|(region, thermostat_type)| RegionThermostat::new(region, thermostat_type))
+                .collect(),
+            distribution_interval: 10,
+            distributions: Vec::new(),
+            energy_interval: 10,
+            energy: 0.0,
+            steps: 0,
+            seed: scenario.seed,
+            rng,
+            chambers: Vec::new(),
+            previous_x: Vec::new(),
+            force_positions: Vec::new(),
+            initial_entropy: Vec::new(),
+        };
+        world.record_transfers();
+        world.initial_entropy = (0..world.container.chamber_count()).map(|chamber| world.chamber_entropy(chamber)).collect();
+        world.update_distributions();
+        world.energy = world.total_energy();
+        world
+    }
+
+    /// Advances the simulation by `dt` seconds, split evenly over `substeps` sub-steps.
+    ///
+    /// In event-driven mode `dt` is covered exactly, collision by collision, instead.
+    pub fn step(&mut self, dt: f32) {
+        self.step_dynamics(dt);
+        self.container.pressure.sample(dt);
+        let kt = self.average_kinetic_energy();
+        for partition in &mut self.container.partitions {
+            partition.demon.observe(&self.particles, dt);
+            partition.ledger.settle(kt);
+        }
+        self.container.tick_demons(dt);
+
+        self.steps += 1;
+        if self.steps.is_multiple_of(self.distribution_interval.max(1) as u64) {
+            self.update_distributions();
+        }
+        if self.steps.is_multiple_of(self.energy_interval.max(1) as u64) {
+            self.energy = self.total_energy();
+        }
+    }
+
+    fn step_dynamics(&mut self, dt: f32) {
+        if let Some(engine) = self.event_driven.as_mut() {
+            engine.advance(&mut self.particles, &mut self.container, &mut self.rng, dt);
+            self.record_transfers();
+            if !self.thermostats.is_empty() {
+                self.apply_thermostats(dt);
+                // The thermostats changed velocities, so every prediction is stale.
+                if let Some(engine) = self.event_driven.as_mut() {
+                    engine.reset(&self.particles, &self.container);
+                }
+            }
+            return;
+        }
+        let new_dt = dt / self.substeps as f32;
+        for _ in 0..self.substeps {
+            self.compute_single_interaction(new_dt);
+        }
+    }
+
+    /// Forgets the forces kept from the last step, e.g. after the force evaluation was changed.
+    pub fn invalidate_forces(&mut self) {
+        self.force_positions.clear();
+    }
+
+    /// Gives every demon the same sorting rule, starting the new policy afresh.
+    pub fn set_demon_policy(&mut self, policy_type: DemonPolicyType) {
+        for partition in &mut self.container.partitions {
+            partition.set_policy(policy_type);
+        }
+    }
+
+    /// Switches between time-stepping and event-driven hard-disc dynamics.
+    ///
+    /// The event-driven engine only knows the rectangular walls and fixed partitions, so it
+    /// stays off while the container has extra geometry or a Szilard engine.
+    pub fn toggle_event_driven(&mut self) {
+        self.event_driven = match self.event_driven {
+            Some(_) => None,
+            None if !self.container.shapes.is_empty() || self.container.szilard.is_some() => None,
+            None => Some(EventDriven::new(&self.particles, &self.container)),
+        };
+    }
+
+    /// Switches the Barnes–Hut evaluator for `CoulombLaw` on or off.
+    ///
+    /// The tree does not see periodic images, so it stays off while either axis is periodic.
+    pub fn toggle_barnes_hut(&mut self) {
+        self.barnes_hut = match self.barnes_hut {
+            Some(_) => None,
+            None if self.container.periodic_x || self.container.periodic_y => None,
+            None => Some(BarnesHut::new(0.5)),
+        };
+        self.invalidate_forces();
+    }
+
+    /// Whether the Barnes–Hut evaluator takes care of `CoulombLaw` instead of the pair loop.
+    fn uses_barnes_hut(&self) -> bool {
+        self.barnes_hut.is_some() && !self.container.periodic_x && !self.container.periodic_y
+    }
+
+    pub fn average_kinetic_energy(&self) -> f32 {
+        let tot: f32 = self.particles.iter().len() as f32;
+        self.kinetic_energy() / tot
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles.iter().map(|particle| particle.kinetic_energy()).sum()
+    }
+
+    /// Total potential energy: the pair energy summed over all pairs plus the field energy.
+    ///
+    /// Along periodic axes each pair is counted once, at its minimum-image separation. Pairs are
+    /// skipped altogether when no law has a potential, and only neighbours on the spatial grid
+    /// are visited for finite-range laws.
+    pub fn potential_energy(&self) -> f32 {
+        let law = build_composite_law(&self.laws, &self.fields, self.container.boundaries);
+        let mut total = 0.0;
+        for particle in &self.particles {
+            total += law.field_energy(particle);
+        }
+        if !law.has_potential() {
+            return total;
+        }
+        let pair_energy = |i: usize, j: usize| {
+            let (particle_i, particle_j) = (&self.particles[i], &self.particles[j]);
+            let offset = self.container.image_offset(particle_i.position, particle_j.position);
+            law.potential_energy(particle_i, particle_j, offset)
+        };
+        match law.range() {
+            Some(range) if self.broad_phase => {
+                let grid = SpatialGrid::new(&self.particles, &self.container, range);
+                for (i, j) in grid.pairs(&self.particles) {
+                    total += pair_energy(i, j);
+                }
+            }
+            _ => {
+                for i in 0..self.particles.len() {
+                    for j in (i + 1)..self.particles.len() {
+                        total += pair_energy(i, j);
+                    }
+                }
+            }
+        }
+        total
+    }
+
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy() + self.potential_energy()
+    }
+
+    /// Kinetic temperature of the particles in chamber `chamber`.
+    pub fn chamber_temperature(&self, chamber: usize) -> f32 {
+        temperature(&self.particles, &Region::Chamber(chamber).select(&self.particles, &self.container))
+    }
+
+    /// Entropy of the gas in chamber `chamber`, treated as a 2D ideal gas:
+    /// `S = N (ln(A kT / N) + 2)` with `k = 1`, up to a constant per particle that depends on the
+    /// particle mass and Planck's constant.
+    pub fn chamber_entropy(&self, chamber: usize) -> f32 {
+        let count = Region::Chamber(chamber).select(&self.particles, &self.container).len() as f32;
+        let kt = self.chamber_temperature(chamber);
+        if count == 0.0 || kt <= 0.0 {
+            return 0.0;
+        }
+        count * ((self.container.chamber_area(chamber) * kt / count).ln() + 2.0)
+    }
+
+    /// Pressure an ideal gas would exert at the density and temperature of chamber `chamber`,
+    /// `P = N kT / A` in 2D, to compare with the measured `ParticleContainer::chamber_pressure`.
+    pub fn ideal_gas_pressure(&self, chamber: usize) -> f32 {
+        let count = Region::Chamber(chamber).select(&self.particles, &self.container).len() as f32;
+        count * self.chamber_temperature(chamber) / self.container.chamber_area(chamber)
+    }
+
+    /// Rebuilds the kinetic-energy histogram of every chamber, in 16 bins up to `8 kT`.
+    pub fn update_distributions(&mut self) {
+        self.distributions = (0..self.container.chamber_count())
+            .map(|chamber| {
+                let selected = Region::Chamber(chamber).select(&self.particles, &self.container);
+                EnergyHistogram::new(&self.particles, &selected, 16, 8.0)
+            })
+            .collect();
+    }
+
+    /// Change of each chamber's entropy since the world was created.
+    ///
+    /// Together with `DemonLedger::erasure_entropy` this checks the second law: the sum over the
+    /// chambers plus the erasure entropy of every demon should not be negative.
+    pub fn entropy_change(&self) -> Vec<f32> {
+        self.initial_entropy
+            .iter()
+            .enumerate()
+            .map(|(chamber, initial)| self.chamber_entropy(chamber) - initial)
+            .collect()
+    }
+
+    /// Number density of particles in `bins` horizontal slabs of equal height, from the ceiling
+    /// down to the ground.
+    pub fn density_profile(&self, bins: usize) -> Vec<f32> {
+        let top = self.container.boundaries.y;
+        let height = self.container.boundaries.z - top;
+        let width = self.container.boundaries.w - self.container.boundaries.x;
+        let slab = height / bins as f32;
+        let mut counts = vec![0.0; bins];
+        for particle in &self.particles {
+            let bin = ((particle.position.y - top) / slab).floor().clamp(0.0, (bins - 1) as f32) as usize;
+            counts[bin] += 1.0;
+        }
+        counts.iter().map(|count| count / (slab * width)).collect()
+    }
+
+    /// Barometric density profile `n(y) ∝ exp(-m g (y_ground - y) / kT)` expected under a uniform
+    /// gravitational acceleration `g` (pointing towards the ground) at the current temperature.
+    ///
+    /// Uses the same slabs as `density_profile` and the same total particle count, so the two can
+    /// be compared directly. In 2D, `kT` is the average kinetic energy per particle.
+    pub fn barometric_profile(&self, g: f32, bins: usize) -> Vec<f32> {
+        let top = self.container.boundaries.y;
+        let height = self.container.boundaries.z - top;
+        let width = self.container.boundaries.w - self.container.boundaries.x;
+        let slab = height / bins as f32;
+        let kt = self.average_kinetic_energy();
+        let mass = self.particles.iter().map(|particle| particle.mass).sum::<f32>() / self.particles.len() as f32;
+
+        let weights: Vec<f32> = (0..bins)
+            .map(|bin| {
+                let depth_from_ground = height - (bin as f32 + 0.5) * slab;
+                (-mass * g * depth_from_ground / kt).exp()
+            })
+            .collect();
+        let total: f32 = weights.iter().sum();
+        let num = self.particles.len() as f32;
+        weights.iter().map(|weight| num * weight / total / (slab * width)).collect()
+    }
+
+    /// Builds the laws for one step, split into the impulsive laws and the force laws, the
+    /// latter carrying the external fields.
+    ///
+    /// Each part keeps the order of `laws`. `CoulombLaw` is left out of the force laws when the
+    /// Barnes–Hut evaluator takes care of it.
+    fn build_laws(&self) -> (CompositeLaw, CompositeLaw) {
+        let mut impulsive: Vec<Box<dyn InteractionLaw>> = Vec::new();
+        let mut forces: Vec<Box<dyn InteractionLaw>> = Vec::new();
+        for &law_type in &self.laws {
+            if law_type == InteractionLawType::CoulombLaw && self.uses_barnes_hut() {
+                continue;
+            }
+            let law = build_interaction_law(law_type);
+            if law.is_impulsive() {
+                impulsive.push(law);
+            } else {
+                forces.push(law);
+            }
+        }
+        let fields = self.fields.iter().map(|&field_type| build_external_field(field_type, self.container.boundaries)).collect();
+        (CompositeLaw::new(impulsive, Vec::new()), CompositeLaw::new(forces, fields))
+    }
+
+    pub fn compute_single_interaction(&mut self, dt: f32) {
+        let (impulsive, forces) = self.build_laws();
+        let tree_coulomb = self.laws.contains(&InteractionLawType::CoulombLaw) && self.uses_barnes_hut();
+        let integrator = build_integrator(self.integrator);
+
+        // Contact laws change velocities and positions directly, so they are resolved once per step.
+        if !impulsive.laws.is_empty() {
+            resolve_pairs(&mut self.particles, &impulsive, &self.container, self.broad_phase);
+        }
+
+        // Force laws and fields are re-evaluated as often as the integrator requires. They only
+        // depend on positions, so the forces left from the last step are reused unless contacts,
+        // bounces or the piston have moved a particle since.
+        let fresh = self.forces_fresh();
+        let barnes_hut = &mut self.barnes_hut;
+        let container = &self.container;
+        let broad_phase = self.broad_phase;
+        let valid = integrator.step(&mut self.particles, dt, &mut |particles| {
+            for particle in particles.iter_mut() {
+                particle.reset_force();
+                forces.apply_fields(particle);
+            }
+            if let (true, Some(tree)) = (tree_coulomb, barnes_hut.as_mut()) {
+                tree.accumulate_forces(particles, &build_coulomb_law());
+            }
+            if !forces.laws.is_empty() {
+                resolve_pairs(particles, &forces, container, broad_phase);
+            }
+        }, fresh);
+        self.force_positions.clear();
+        if valid {
+            self.force_positions.extend(self.particles.iter().map(|particle| particle.position));
+        }
+
+        // Move the Szilard engine's piston before the particles are bounced off it.
+        self.container.advance_szilard(&self.particles, dt);
+
+        // Check physics boundaries.
+        for (index, particle) in self.particles.iter_mut().enumerate() {
+            self.container.collision(index, particle, &mut self.rng);
+        }
+        self.record_transfers();
+
+        self.apply_thermostats(dt);
+    }
+
+    /// Whether `Particle::force` still holds the forces at the particles' current positions.
+    fn forces_fresh(&self) -> bool {
+        self.force_positions.len() == self.particles.len()
+            && self.particles.iter().zip(&self.force_positions).all(|(particle, position)| particle.position == *position)
+    }
+
+    /// Books every particle that moved into a neighbouring chamber since the last call as a
+    /// transfer through the partition between them, carrying its current kinetic energy.
+    ///
+    /// Jumps over several chambers are not booked, and neither are wraps around a periodic box,
+    /// which move a particle by more than half the box width.
+    fn record_transfers(&mut self) {
+        if self.chambers.len() != self.particles.len() {
+            self.chambers = self.particles.iter().map(|particle| self.container.chamber_of(particle)).collect();
+            self.previous_x = self.particles.iter().map(|particle| particle.position.x).collect();
+            return;
+        }
+        let half_width = 0.5 * self.container.size().x;
+        for (index, particle) in self.particles.iter().enumerate() {
+            let (from, to) = (self.chambers[index], self.container.chamber_of(particle));
+            // A particle that wrapped around crossed an edge of the box rather than a partition.
+            let wrapped = (particle.position.x - self.previous_x[index]).abs() > half_width;
+            if !wrapped && to == from + 1 {
+                self.container.partitions[from].ledger.record_transfer(Direction::LeftToRight, particle.kinetic_energy());
+            } else if !wrapped && to + 1 == from {
+                self.container.partitions[to].ledger.record_transfer(Direction::RightToLeft, particle.kinetic_energy());
+            }
+            self.chambers[index] = to;
+            self.previous_x[index] = particle.position.x;
+        }
+    }
+
+    fn apply_thermostats(&mut self, dt: f32) {
+        for thermostat in &mut self.thermostats {
+            thermostat.apply(&mut self.particles, &self.container, dt, &mut self.rng);
+        }
+    }
+}
+
+/// Whether two discs overlap, at their minimum-image separation along periodic axes.
+fn overlaps(container: &ParticleContainer, a: &Particle, b: &Particle) -> bool {
+    let offset = container.image_offset(a.position, b.position);
+    let dx = b.position.x + offset.x - a.position.x;
+    let dy = b.position.y + offset.y - a.position.y;
+    let reach = a.radius + b.radius;
+    dx * dx + dy * dy < reach * reach
+}
+
+/// Resolves `law` for every interacting pair, through the spatial grid when the law has a
+/// finite range and `broad_phase` is enabled. Periodic axes use the minimum-image convention.
+fn resolve_pairs(particles: &mut [Particle], law: &dyn InteractionLaw, container: &ParticleContainer, broad_phase: bool) {
+    match law.range() {
+        Some(range) if broad_phase => {
+            let mut grid = SpatialGrid::new(particles, container, range);
+            grid.resolve_pairs(particles, law, container);
+        }
+        _ => resolve_all_pairs(particles, law, container),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::physics::container::WallKind;
+    use super::*;
+
+    /// Positions and velocities of every particle, bit for bit.
+    fn state_bits(world: &World) -> Vec<[u32; 4]> {
+        world.particles
+            .iter()
+            .map(|p| [p.position.x.to_bits(), p.position.y.to_bits(), p.velocity.x.to_bits(), p.velocity.y.to_bits()])
+            .collect()
+    }
+
+    fn run(scenario: Scenario, steps: usize) -> World {
+        let mut world = World::new(1000.0, 700.0, scenario);
+        for _ in 0..steps {
+            world.step(1.0 / 60.0);
+        }
+        world
+    }
+
+    #[test]
+    fn same_seed_runs_are_bit_identical() {
+        for scenario in [Scenario::noisy_demon(100), Scenario::canonical(100), Scenario::charged_gas(100)] {
+            let first = run(Scenario { seed: 7, ..scenario.clone() }, 60);
+            let second = run(Scenario { seed: 7, ..scenario.clone() }, 60);
+            assert_eq!(state_bits(&first), state_bits(&second));
+            let other = run(Scenario { seed: 8, ..scenario }, 60);
+            assert_ne!(state_bits(&first), state_bits(&other));
+        }
+    }
+
+    #[test]
+    fn reused_forces_give_the_same_trajectories_as_fresh_ones() {
+        for scenario in [Scenario::lennard_jones_fluid(100), Scenario::plasma(100)] {
+            let mut reusing = World::new(1000.0, 700.0, scenario.clone());
+            let mut fresh = World::new(1000.0, 700.0, scenario);
+            for _ in 0..300 {
+                reusing.compute_single_interaction(1.0 / 1200.0);
+                fresh.invalidate_forces();
+                fresh.compute_single_interaction(1.0 / 1200.0);
+            }
+            assert_eq!(state_bits(&reusing), state_bits(&fresh));
+        }
+    }
+
+    #[test]
+    fn a_bounce_off_a_wall_forces_a_new_evaluation() {
+        let mut world = World::new(1000.0, 700.0, Scenario::lennard_jones_fluid(50));
+        world.compute_single_interaction(1.0 / 1200.0);
+        assert!(world.forces_fresh());
+
+        // Send a particle into the left wall, which moves it back after the forces were computed.
+        world.particles[0].position.x = 5.5;
+        world.particles[0].velocity.x = -1000.0;
+        world.compute_single_interaction(1.0 / 1200.0);
+        assert!(!world.forces_fresh());
+
+        world.compute_single_interaction(1.0 / 1200.0);
+        assert!(world.forces_fresh());
+    }
+
+    #[test]
+    fn particles_are_placed_without_overlaps_unless_the_box_is_too_crowded() {
+        let world = World::new(1000.0, 700.0, Scenario::lennard_jones_fluid(300));
+        for (i, a) in world.particles.iter().enumerate() {
+            assert!(world.particles[i + 1..].iter().all(|b| !overlaps(&world.container, a, b)));
+        }
+
+        // A hundred discs cannot fit into a 60 x 60 box, so placement gives up on them after
+        // `MAX_PLACEMENT_MISSES` misses in a row instead of trying forever.
+        let crowded = World::new(60.0, 60.0, Scenario::ideal_gas(100));
+        assert_eq!(crowded.particles.len(), 100);
+        let overlapping = (0..100).any(|i| (i + 1..100).any(|j| overlaps(&crowded.container, &crowded.particles[i], &crowded.particles[j])));
+        assert!(overlapping);
+    }
+
+    #[test]
+    fn barnes_hut_stays_off_in_periodic_boxes() {
+        let mut world = World::new(1000.0, 700.0, Scenario { periodic_x: true, ..Scenario::plasma(10) });
+        world.toggle_barnes_hut();
+        assert!(world.barnes_hut.is_none());
+
+        let mut world = World::new(1000.0, 700.0, Scenario::plasma(10));
+        world.toggle_barnes_hut();
+        assert!(world.barnes_hut.is_some());
+    }
+    #[test]
+    fn a_crossing_is_booked_once_per_direction_with_its_kinetic_energy() {
+        let mut world = World::new(1000.0, 700.0, Scenario::ideal_gas(1));
+        world.particles = vec![Particle::at(480.0, 350.0, 100.0, 0.0)];
+        world.chambers.clear();
+        // Through the open door, off the right wall and back through the door.
+        let mut booked = Vec::new();
+        for _ in 0..720 {
+            world.step(1.0 / 60.0);
+            let transfers = world.container.partitions[0].ledger.transfers;
+            if booked.last() != Some(&transfers) {
+                booked.push(transfers);
+            }
+        }
+        assert_eq!(booked, vec![[0, 0], [1, 0], [1, 1]]);
+        assert_eq!(world.container.partitions[0].ledger.energy, [5000.0, 5000.0]);
+    }
+
+    #[test]
+    fn the_cascade_has_four_chambers_credited_from_left_to_right() {
+        // Partitions listed out of order are sorted into a row.
+        let mut scenario = Scenario::cascade(4);
+        scenario.partitions.reverse();
+        let mut world = World::new(1000.0, 700.0, scenario);
+        assert_eq!(world.container.chamber_count(), 4);
+        let walls: Vec<f32> = world.container.partitions.iter().map(|partition| partition.x).collect();
+        assert_eq!(walls, vec![250.0, 500.0, 750.0]);
+        for chamber in 0..4 {
+            let left = 250.0 * chamber as f32;
+            assert_eq!(world.container.chamber_bounds(chamber), (left, left + 250.0));
+            assert_eq!(world.container.chamber_of(&Particle::at(left + 125.0, 350.0, 0.0, 0.0)), chamber);
+            assert_eq!(world.container.chamber_of(&Particle::at(left, 350.0, 0.0, 0.0)), chamber);
+        }
+
+        // Chamber `c` holds two particles moving at speed 100 (c + 1).
+        world.particles = (0..8)
+            .map(|i| Particle::at(125.0 + 250.0 * (i / 2) as f32, 200.0 + 300.0 * (i % 2) as f32, 0.0, 100.0 * (i / 2 + 1) as f32))
+            .collect();
+        for chamber in 0..4 {
+            let speed = 100.0 * (chamber + 1) as f32;
+            assert_eq!(world.chamber_temperature(chamber), 0.5 * speed * speed);
+        }
+
+        // One particle through the door of the last wall, one back through the door of the first.
+        world.particles = vec![Particle::at(740.0, 350.0, 300.0, 0.0), Particle::at(260.0, 350.0, -200.0, 0.0)];
+        world.chambers.clear();
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let ledgers: Vec<[u32; 2]> = world.container.partitions.iter().map(|partition| partition.ledger.transfers).collect();
+        assert_eq!(ledgers, vec![[0, 1], [0, 0], [1, 0]]);
+        assert_eq!(world.container.partitions[2].ledger.energy, [45000.0, 0.0]);
+        assert_eq!(world.container.partitions[0].ledger.energy, [0.0, 20000.0]);
+        assert_eq!(world.chamber_temperature(3), 45000.0);
+        assert_eq!(world.chamber_temperature(0), 20000.0);
+        assert_eq!(world.chamber_temperature(1), 0.0);
+    }
+
+    #[test]
+    fn a_gas_under_gravity_settles_into_the_barometric_profile() {
+        // An ideal gas held at a fixed temperature by the walls: denser hard discs would pile up
+        // less at the bottom than the ideal-gas formula allows for.
+        let scenario = Scenario {
+            walls: [WallKind::Thermal { temperature: 12000.0 }; 4],
+            laws: Vec::new(),
+            ..Scenario::gravity(600)
+        };
+        let mut world = World::new(1000.0, 700.0, scenario);
+        let bins = 5;
+        let (mut measured, mut expected) = (vec![0.0; bins], vec![0.0; bins]);
+        for step in 0..1800 {
+            world.step(1.0 / 60.0);
+            if step >= 600 {
+                measured.iter_mut().zip(world.density_profile(bins)).for_each(|(total, density)| *total += density);
+                expected.iter_mut().zip(world.barometric_profile(30.0, bins)).for_each(|(total, density)| *total += density);
+            }
+        }
+        // The density rises about fourfold from the ceiling to the ground.
+        assert!(expected[bins - 1] > 3.0 * expected[0]);
+        for (measured, expected) in measured.iter().zip(&expected) {
+            assert!((measured / expected - 1.0).abs() < 0.1, "{:?} vs {:?}", measured, expected);
+        }
+    }
+
+    #[test]
+    fn chamber_entropy_is_that_of_a_2d_ideal_gas() {
+        let world = World::new(1000.0, 700.0, Scenario::ideal_gas(200));
+        for chamber in 0..2 {
+            let members: Vec<&Particle> = world.particles.iter().filter(|p| (p.position.x < 500.0) == (chamber == 0)).collect();
+            let count = members.len() as f32;
+            let kt = members.iter().map(|p| p.kinetic_energy()).sum::<f32>() / count;
+            let expected = count * ((500.0 * 700.0 * kt / count).ln() + 2.0);
+            assert!((world.chamber_entropy(chamber) / expected - 1.0).abs() < 1e-5, "{} vs {}", world.chamber_entropy(chamber), expected);
+        }
+        assert!(world.entropy_change().iter().all(|&change| change == 0.0));
+    }
+
+    #[test]
+    fn a_granular_gas_cools_while_an_elastic_one_does_not() {
+        let energy_ratio = |scenario: Scenario| {
+            let mut world = World::new(600.0, 400.0, scenario);
+            let initial_energy = world.kinetic_energy();
+            for _ in 0..300 {
+                world.step(1.0 / 60.0);
+            }
+            world.kinetic_energy() / initial_energy
+        };
+        let elastic = energy_ratio(Scenario::ideal_gas(150));
+        let granular = energy_ratio(Scenario::granular_gas(150));
+        assert!((elastic - 1.0).abs() < 0.02, "{}", elastic);
+        assert!(granular < 0.8, "{}", granular);
+    }
+
+    #[test]
+    fn charged_discs_both_attract_and_collide() {
+        // ImpulseCollision and CoulombLaw, split by build_laws into an impulsive and a force group.
+        let mut world = World::new(1000.0, 700.0, Scenario::charged_gas(2));
+        let charge = 0.001234;
+        world.particles = vec![
+            Particle { charge, ..Particle::at(300.0, 350.0, 0.0, 0.0) },
+            Particle { charge: -charge, ..Particle::at(340.0, 350.0, 0.0, 0.0) },
+        ];
+        world.chambers.clear();
+        let separation = |world: &World| world.particles[1].position.x - world.particles[0].position.x;
+
+        let history: Vec<f32> = (0..240)
+            .map(|_| {
+                world.step(1.0 / 60.0);
+                separation(&world)
+            })
+            .collect();
+        let contact = (0..history.len()).min_by(|&a, &b| history[a].total_cmp(&history[b])).unwrap();
+
+        // The opposite charges pull the discs together until they touch, without passing through...
+        assert!((history[contact] - 10.0).abs() < 0.5, "closest approach {}", history[contact]);
+        // ...they bounce elastically back out to about where they started...
+        let rebound = history[contact..].iter().copied().fold(0.0, f32::max);
+        assert!(rebound > 35.0, "rebound {}", rebound);
+        // ...and the attraction pulls them in again.
+        assert!(*history.last().unwrap() < rebound - 1.0);
+    }
+}
@@ -0,0 +1,69 @@
+use ggez::graphics::Color;
+use crate::physics::particles::Particle;
+
+
+/// Colours the particle by its kinetic energy relative to the average `avg`, from sky blue
+/// through pale yellow and coral orange to lavender red.
+pub fn kinetic_energy_color(particle: &Particle, avg: &f32) -> Color {
+    let self_ke = particle.kinetic_energy();
+    // Normalize kinetic energy so an average particle roughly maps to 50.
+    let normalized = if *avg > 0.0 { (self_ke / *avg) * 50.0 } else { 0.0 };
+    let t = normalized.clamp(0.0, 100.0);
+    // rgb(100,136,234)
+    // Define anchor colors with explicit f32 annotations.
+    // Sky Blue: RGB (175, 218, 237)
+    // Pale Yellow: RGB (255, 245, 205)
+    // Coral Orange: RGB (255, 135, 117)
+    // Lavender Red: RGB (216, 160, 198
+
+    let blue: (f32, f32, f32)   = (175.0_f32,   218.0_f32, 237.0_f32);
+    let yellow: (f32, f32, f32) = (255.0_f32, 245.0_f32,   205.0_f32);
+    let orange: (f32, f32, f32) = (255.0_f32, 135.0_f32,   117.0_f32);
+    let red: (f32, f32, f32)    = (216.0_f32,   160.0_f32,   198.0_f32);
+
+    // Smoothstep function for smooth interpolation.
+    let smoothstep = |edge0: f32, edge1: f32, x: f32| -> f32 {
+        let factor = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+        factor * factor * (3.0 - 2.0 * factor)
+    };
+
+    // Helper closure to interpolate between two colors.
+    let interpolate_color = |start: (f32, f32, f32), end: (f32, f32, f32), factor: f32| -> (u8, u8, u8) {
+        let r = start.0 + (end.0 - start.0) * factor;
+        let g = start.1 + (end.1 - start.1) * factor;
+        let b = start.2 + (end.2 - start.2) * factor;
+        (r.round() as u8, g.round() as u8, b.round() as u8)
+    };
+
+    // Map the normalized value (t) to a color.
+    let (r, g, b) = if t <= 20.0 {
+        // Directly return blue.
+        (blue.0.round() as u8, blue.1.round() as u8, blue.2.round() as u8)
+    } else if t <= 40.0 {
+        let factor = smoothstep(20.0, 40.0, t);
+        interpolate_color(blue, yellow, factor)
+    } else if t <= 60.0 {
+        let factor = smoothstep(40.0, 60.0, t);
+        interpolate_color(yellow, orange, factor)
+    } else if t <= 80.0 {
+        let factor = smoothstep(60.0, 80.0, t);
+        interpolate_color(orange, red, factor)
+    } else {
+        // Directly return red.
+        (red.0.round() as u8, red.1.round() as u8, red.2.round() as u8)
+    };
+
+    Color::from_rgb(r, g, b)
+}
+
+/// Colours the particle by the sign of its charge: coral for positive, sky blue for
+/// negative and grey for neutral.
+pub fn charge_color(particle: &Particle) -> Color {
+    if particle.charge > 0.0 {
+        Color::from_rgb(255, 135, 117)
+    } else if particle.charge < 0.0 {
+        Color::from_rgb(100, 136, 234)
+    } else {
+        Color::from_rgb(160, 160, 160)
+    }
+}
@@ -1,10 +1,90 @@
 use ggez::{graphics, Context, GameResult};
 use ggez::event::{EventHandler, KeyCode, KeyMods, MouseButton};
 use ggez::graphics::{Color, DrawMode, DrawParam, Mesh, MeshBuilder, Text};
-use ggez::mint::Point2;
-use crate::rendering::state::MainState;
+use ggez::mint::{Point2, Vector2};
+use crate::physics::container::{ParticleContainer, Wall};
+use crate::physics::demon::Direction;
+use crate::physics::fields::FieldType;
+use crate::physics::geometry::Shape;
+use crate::physics::particles::Particle;
+use crate::physics::world::World;
+use crate::rendering::colors::{charge_color, kinetic_energy_color};
+use crate::rendering::state::{ColorMode, MainState};
 
 
+/// Vertical position of the slider in the sidebar, below `rows` rows of readouts.
+fn slider_y(rows: usize) -> f32 {
+    20.0 + 25.0 * rows as f32
+}
+
+/// Name of a chamber in the sidebar: left and right for a single partition, numbers otherwise.
+fn chamber_name(chamber: usize, count: usize) -> String {
+    match (count, chamber) {
+        (2, 0) => "left".to_string(),
+        (2, _) => "right".to_string(),
+        _ => (chamber + 1).to_string(),
+    }
+}
+
+/// Readouts listed at the top of the sidebar: the temperature and pressure of every chamber, the
+/// pressure on the outer walls, the entropy change and H-function of every chamber, then the
+/// demons' bookkeeping: what crossed and the Landauer cost of the measurements, so the second
+/// law can be checked. Transfers and heat are summed over all partitions, and the bits include
+/// those of a Szilard engine's demon. The run's seed comes last.
+fn sidebar_lines(world: &World) -> Vec<String> {
+    let container = &world.container;
+    let chambers = container.chamber_count();
+    let entropy_change = world.entropy_change();
+    let gas_entropy_change: f32 = entropy_change.iter().sum();
+    let ledgers = || container.partitions.iter().map(|partition| &partition.ledger);
+    let transfers = |direction: Direction| ledgers().map(|ledger| ledger.transfers[direction as usize]).sum::<u32>();
+    let szilard_bits = container.szilard.as_ref().map_or(0, |engine| engine.measurements);
+    let szilard_cost = container.szilard.as_ref().map_or(0.0, |engine| engine.landauer_cost());
+    let bits = ledgers().map(|ledger| ledger.measurements).sum::<u32>() + szilard_bits;
+    let erasure_entropy = ledgers().map(|ledger| ledger.erasure_entropy()).sum::<f32>() + szilard_bits as f32 * std::f32::consts::LN_2;
+
+    let mut lines: Vec<String> = (0..chambers)
+        .map(|c| format!("T_{}: {:.2}", chamber_name(c, chambers), world.chamber_temperature(c)))
+        .collect();
+    // Measured pressure of each chamber, with the ideal-gas value N kT / A in brackets.
+    lines.extend((0..chambers).map(|c| {
+        format!("P_{}: {:.2} ({:.2})", chamber_name(c, chambers), container.chamber_pressure(c), world.ideal_gas_pressure(c))
+    }));
+    lines.push(format!("P_L/R: {:.2} {:.2}", container.wall_pressure(Wall::Left), container.wall_pressure(Wall::Right)));
+    lines.push(format!("P_C/G: {:.2} {:.2}", container.wall_pressure(Wall::Ceiling), container.wall_pressure(Wall::Ground)));
+    lines.extend((0..chambers).map(|c| format!("dS_{}: {:.2}", chamber_name(c, chambers), entropy_change[c])));
+    // Boltzmann's H of each chamber's energy histogram and its distance from Maxwell–Boltzmann.
+    for (c, histogram) in world.distributions.iter().enumerate() {
+        lines.push(format!("H_{}: {:.3}", chamber_name(c, chambers), histogram.h_function()));
+        lines.push(format!("KL_{}: {:.3}", chamber_name(c, chambers), histogram.kl_divergence()));
+    }
+    lines.extend([
+        format!("L->R: {}", transfers(Direction::LeftToRight)),
+        format!("R->L: {}", transfers(Direction::RightToLeft)),
+        format!("Heat->L: {:.0}", ledgers().map(|ledger| ledger.heat_to_left()).sum::<f32>()),
+        format!("Bits: {}", bits),
+        format!("Landauer: {:.0}", ledgers().map(|ledger| ledger.landauer_cost).sum::<f32>() + szilard_cost),
+        format!("dS+bits ln2: {:.2}", gas_entropy_change + erasure_entropy),
+    ]);
+    // A slow demon's latency, which is counted in frames rather than seconds.
+    if let Some(limits) = container.partitions.first().map(|partition| partition.limits).filter(|limits| limits.latency > 0) {
+        lines.push(format!("Latency: {} frames", limits.latency));
+    }
+    // The Szilard engine's work per cycle against the bound set by its one bit, and the kinetic
+    // energy its piston lost to the walls.
+    if let Some(engine) = &container.szilard {
+        lines.extend([
+            format!("Cycles: {}", engine.cycles),
+            format!("W/cycle: {:.0}", engine.work_per_cycle()),
+            format!("kT ln2: {:.0}", engine.landauer_work()),
+            format!("Piston loss: {:.0}", engine.dissipated),
+        ]);
+    }
+    // The seed, so the run can be repeated.
+    lines.push(format!("Seed: {}", world.seed));
+    lines
+}
+
 fn draw_slider(
     ctx: &mut Context,
     x: f32,
@@ -32,6 +112,90 @@ fn draw_slider(
     Ok(())
 }
 
+/// Draws a horizontal bar per slab of `measured`, with a tick marking the `expected` value.
+fn draw_profile(
+    ctx: &mut Context,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    measured: &[f32],
+    expected: &[f32],
+) -> GameResult<()> {
+    let max = measured.iter().chain(expected).cloned().fold(0.0_f32, f32::max);
+    if measured.is_empty() || max <= 0.0 {
+        return Ok(());
+    }
+    let bar_height = height / measured.len() as f32;
+
+    let mut mb = MeshBuilder::new();
+    for (bin, (value, model)) in measured.iter().zip(expected).enumerate() {
+        let bar_y = y + bin as f32 * bar_height;
+        let bar = graphics::Rect::new(x, bar_y + 1.0, width * value / max, bar_height - 2.0);
+        mb.rectangle(DrawMode::fill(), bar, Color::from_rgb(175, 218, 237))?;
+        let tick = graphics::Rect::new(x + width * model / max - 1.0, bar_y, 2.0, bar_height);
+        mb.rectangle(DrawMode::fill(), tick, Color::from_rgb(255, 135, 117))?;
+    }
+    let mesh = mb.build(ctx)?;
+    graphics::draw(ctx, &mesh, DrawParam::default())?;
+    Ok(())
+}
+
+/// Adds the outline of a piece of fixed geometry to `mb`, approximating arcs by short lines.
+fn draw_shape(mb: &mut MeshBuilder, shape: &Shape) -> GameResult<()> {
+    let points: Vec<Point2<f32>> = match *shape {
+        Shape::Segment { start, end } => vec![
+            Point2 { x: start.x, y: start.y },
+            Point2 { x: end.x, y: end.y },
+        ],
+        Shape::Arc { centre, radius, start, end } => {
+            let pieces = ((64.0 * (end - start) / std::f32::consts::TAU).ceil() as usize).max(2);
+            (0..=pieces)
+                .map(|i| {
+                    let angle = start + (end - start) * i as f32 / pieces as f32;
+                    Point2 { x: centre.x + radius * angle.cos(), y: centre.y + radius * angle.sin() }
+                })
+                .collect()
+        }
+    };
+    mb.line(&points, 2.0, Color::WHITE)?;
+    Ok(())
+}
+
+/// Shifts at which a disc straddling a periodic edge reappears on the opposite side(s).
+fn ghost_offsets(container: &ParticleContainer, particle: &Particle) -> Vec<Vector2<f32>> {
+    let size = container.size();
+    let bounds = container.boundaries;
+    let mut shifts_x = vec![0.0];
+    if container.periodic_x {
+        if particle.position.x - particle.radius < bounds.x {
+            shifts_x.push(size.x);
+        }
+        if particle.position.x + particle.radius > bounds.w {
+            shifts_x.push(-size.x);
+        }
+    }
+    let mut shifts_y = vec![0.0];
+    if container.periodic_y {
+        if particle.position.y - particle.radius < bounds.y {
+            shifts_y.push(size.y);
+        }
+        if particle.position.y + particle.radius > bounds.z {
+            shifts_y.push(-size.y);
+        }
+    }
+
+    let mut offsets = Vec::new();
+    for &x in &shifts_x {
+        for &y in &shifts_y {
+            if x != 0.0 || y != 0.0 {
+                offsets.push(Vector2 { x, y });
+            }
+        }
+    }
+    offsets
+}
+
 
 impl EventHandler for MainState {
     // Update function remains unchanged.
@@ -50,64 +214,85 @@ impl EventHandler for MainState {
         // Define the sidebar width.
         let sidebar_width = 150.0;
         // The simulation region is the total width minus the sidebar.
-        let sim_width = self.container.boundaries.w - sidebar_width;
+        let sim_width = self.world.container.boundaries.w - sidebar_width;
 
         // Define the simulation region rectangle.
         let simulation_rect = graphics::Rect::new(
-            self.container.boundaries.x,
-            self.container.boundaries.y,
+            self.world.container.boundaries.x,
+            self.world.container.boundaries.y,
             sim_width,
-            self.container.boundaries.z,
+            self.world.container.boundaries.z,
         );
 
         let mut mb = MeshBuilder::new();
         // Draw a white boundary around the simulation region.
         mb.rectangle(DrawMode::stroke(2.0), simulation_rect, Color::WHITE);
 
+        // Draw the container geometry and the obstacles.
+        for shape in &self.world.container.shapes {
+            draw_shape(&mut mb, shape)?;
+        }
+
+        // Draw each partition with its demon's door: a thin green line while it is open, a thick
+        // coral one while it is closed.
+        let boundaries = self.world.container.boundaries;
+        for partition in &self.world.container.partitions {
+            if let Some(door) = partition.door {
+                let x = partition.x;
+                let (top, bottom) = door.span(boundaries);
+                mb.line(&[Point2 { x, y: boundaries.y }, Point2 { x, y: top }], 2.0, Color::WHITE)?;
+                mb.line(&[Point2 { x, y: bottom }, Point2 { x, y: boundaries.z }], 2.0, Color::WHITE)?;
+                let (door_width, door_color) = if door.open {
+                    (1.0, Color::from_rgb(120, 220, 120))
+                } else {
+                    (4.0, Color::from_rgb(255, 135, 117))
+                };
+                mb.line(&[Point2 { x, y: top }, Point2 { x, y: bottom }], door_width, door_color)?;
+            }
+        }
+
+        // Draw the Szilard engine's piston while it is in the box.
+        if let Some(piston) = self.world.container.szilard.as_ref().and_then(|engine| engine.piston) {
+            let x = piston.x;
+            let piston_color = Color::from_rgb(240, 200, 80);
+            mb.line(&[Point2 { x, y: boundaries.y }, Point2 { x, y: boundaries.z }], 4.0, piston_color)?;
+        }
+
         // Compute the average kinetic energy for particle coloring.
-        let avg = self.average_kinetic_energy();
+        let avg = self.world.average_kinetic_energy();
 
         // Draw each particle as a circle.
-        for particle in &self.particles {
+        for particle in &self.world.particles {
+            let color = match self.color_mode {
+                ColorMode::KineticEnergy => kinetic_energy_color(particle, &avg),
+                ColorMode::ChargeSign => charge_color(particle),
+            };
             mb.circle(
                 DrawMode::fill(),
                 particle.position,
                 particle.radius,
                 0.1,
-                particle.color(&avg),
+                color,
             );
+
+            // Draw faded ghost images where the disc wraps around a periodic edge.
+            let ghost_color = Color { a: 0.4, ..color };
+            for offset in ghost_offsets(&self.world.container, particle) {
+                let ghost = Point2 { x: particle.position.x + offset.x, y: particle.position.y + offset.y };
+                mb.circle(DrawMode::fill(), ghost, particle.radius, 0.1, ghost_color)?;
+            }
         }
 
         let sim_mesh = mb.build(ctx)?;
         graphics::draw(ctx, &sim_mesh, graphics::DrawParam::default())?;
 
         // --- Sidebar UI Elements ---
-        // Compute temperature readings as before.
-        let middle_x = simulation_rect.w / 2.0;
-        let mut left_sum = 0.0;
-        let mut left_count = 0;
-        let mut right_sum = 0.0;
-        let mut right_count = 0;
-
-        for particle in &self.particles {
-            let ke = 0.5 * (particle.velocity.x.powi(2) + particle.velocity.y.powi(2));
-            if particle.position.x < middle_x {
-                left_sum += ke;
-                left_count += 1;
-            } else {
-                right_sum += ke;
-                right_count += 1;
-            }
-        }
-        let left_temp = if left_count > 0 { left_sum / left_count as f32 } else { 0.0 };
-        let right_temp = if right_count > 0 { right_sum / right_count as f32 } else { 0.0 };
-
         // Define the sidebar region (on the right side of the screen).
         let sidebar_rect = graphics::Rect::new(
             sim_width, // starting x coordinate of the sidebar
             0.0,
             sidebar_width,
-            self.container.boundaries.z,
+            self.world.container.boundaries.z,
         );
 
         // Draw a background for the sidebar.
@@ -116,21 +301,16 @@ impl EventHandler for MainState {
             .build(ctx)?;
         graphics::draw(ctx, &sidebar_bg, graphics::DrawParam::default())?;
 
-        // Create text objects to display temperature readings.
-        let left_text = Text::new(format!("T_left: {:.2}", left_temp));
-        let right_text = Text::new(format!("T_right: {:.2}", right_temp));
-
-        // Position the text within the sidebar.
-        let text_dest1 = Point2 { x: sim_width + 10.0, y: 10.0 };
-        let text_dest2 = Point2 { x: sim_width + 10.0, y: 40.0 };
-
-        graphics::draw(ctx, &left_text, (text_dest1, Color::WHITE))?;
-        graphics::draw(ctx, &right_text, (text_dest2, Color::WHITE))?;
+        let lines = sidebar_lines(&self.world);
+        for (row, line) in lines.iter().enumerate() {
+            let dest = Point2 { x: sim_width + 10.0, y: 10.0 + 25.0 * row as f32 };
+            graphics::draw(ctx, &Text::new(line.as_str()), (dest, Color::WHITE))?;
+        }
 
         // Draw the slider.
         // Define slider dimensions and position.
         let slider_x = sim_width + 10.0;
-        let slider_y = 80.0;
+        let slider_y = slider_y(lines.len());
         let slider_width = sidebar_width - 20.0;
         let slider_height = 20.0;
         draw_slider(ctx, slider_x, slider_y, slider_width, slider_height, self.slider_value)?;
@@ -140,6 +320,45 @@ impl EventHandler for MainState {
         let slider_val_dest = Point2 { x: slider_x, y: slider_y + slider_height + 5.0 };
         graphics::draw(ctx, &slider_val_text, (slider_val_dest, Color::WHITE))?;
 
+        // Report the Barnes–Hut force error while it is being measured.
+        if let Some(error) = self.world.barnes_hut.as_ref().and_then(|tree| tree.last_error) {
+            let error_text = Text::new(format!("BH error: {:.4}", error));
+            let error_dest = Point2 { x: slider_x, y: slider_val_dest.y + 30.0 };
+            graphics::draw(ctx, &error_text, (error_dest, Color::WHITE))?;
+        }
+
+        // Show the integrator and the total energy so schemes can be compared.
+        let integrator_text = match self.world.event_driven {
+            Some(_) => Text::new("Event-driven"),
+            None => Text::new(format!("{:?}", self.world.integrator)),
+        };
+        let integrator_dest = Point2 { x: slider_x, y: slider_val_dest.y + 60.0 };
+        graphics::draw(ctx, &integrator_text, (integrator_dest, Color::WHITE))?;
+
+        let energy_text = Text::new(format!("E_total: {:.1}", self.world.energy));
+        let energy_dest = Point2 { x: slider_x, y: slider_val_dest.y + 90.0 };
+        graphics::draw(ctx, &energy_text, (energy_dest, Color::WHITE))?;
+
+        let policy = self.world.container.partitions.first().map_or("none", |partition| partition.policy.name());
+        let demon_text = Text::new(format!("Demon: {}", policy));
+        let demon_dest = Point2 { x: slider_x, y: slider_val_dest.y + 120.0 };
+        graphics::draw(ctx, &demon_text, (demon_dest, Color::WHITE))?;
+
+        // Under gravity, compare the density profile with the barometric formula.
+        let gravity = self.world.fields.iter().find_map(|field| match field {
+            FieldType::UniformGravity(g) => Some(g.y),
+            _ => None,
+        });
+        if let Some(g) = gravity {
+            let bins = 10;
+            let profile_text = Text::new("Density vs. barometric");
+            let profile_dest = Point2 { x: slider_x, y: slider_val_dest.y + 160.0 };
+            graphics::draw(ctx, &profile_text, (profile_dest, Color::WHITE))?;
+            let measured = self.world.density_profile(bins);
+            let expected = self.world.barometric_profile(g, bins);
+            draw_profile(ctx, slider_x, profile_dest.y + 25.0, slider_width, 150.0, &measured, &expected)?;
+        }
+
         graphics::present(ctx)?;
         Ok(())
     }
@@ -147,9 +366,9 @@ impl EventHandler for MainState {
     fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
         // Check if the click is inside the slider area.
         let sidebar_width = 150.0;
-        let sim_width = self.container.boundaries.w - sidebar_width;
+        let sim_width = self.world.container.boundaries.w - sidebar_width;
         let slider_x = sim_width + 10.0;
-        let slider_y = 80.0;
+        let slider_y = slider_y(sidebar_lines(&self.world).len());
         let slider_width = sidebar_width - 20.0;
         let slider_height = 20.0;
         let slider_rect = graphics::Rect::new(slider_x, slider_y, slider_width, slider_height);
@@ -161,9 +380,9 @@ impl EventHandler for MainState {
         } else {
             // Other mouse button events.
             if button == MouseButton::Left {
-                self.container.demon_looking = false;
+                self.world.container.demon_looking = false;
             } else if button == MouseButton::Right {
-                self.container.demon_looking = true;
+                self.world.container.demon_looking = true;
             }
         }
     }
@@ -172,6 +391,25 @@ impl EventHandler for MainState {
         match keycode {
             KeyCode::Space => { self.pause_play(); },
             KeyCode::Right => { self.update_state(_ctx); },
+            KeyCode::C => {
+                self.color_mode = match self.color_mode {
+                    ColorMode::KineticEnergy => ColorMode::ChargeSign,
+                    ColorMode::ChargeSign => ColorMode::KineticEnergy,
+                };
+            },
+            KeyCode::H => { self.world.toggle_event_driven(); },
+            KeyCode::I => { self.world.integrator = self.world.integrator.next(); },
+            KeyCode::P => {
+                if let Some(partition) = self.world.container.partitions.first() {
+                    self.world.set_demon_policy(partition.policy.next());
+                }
+            },
+            KeyCode::B => { self.world.toggle_barnes_hut(); },
+            KeyCode::E => {
+                if let Some(tree) = self.world.barnes_hut.as_mut() {
+                    tree.measure_error = !tree.measure_error;
+                }
+            },
             _ => {}
         }
     }
@@ -1,4 +1,5 @@
 pub mod example;
 pub mod simulation;
+mod colors;
 mod events;
 mod state;
@@ -1,15 +1,15 @@
 use ggez::event::{self};
 use ggez::{ContextBuilder, GameResult};
 use ggez::conf::{WindowSetup, WindowMode};
-use crate::physics::laws::InteractionLawType;
+use crate::physics::scenario::Scenario;
 use crate::rendering::state::MainState;
 
 
-pub fn run(num: u32, law: InteractionLawType) -> GameResult {
+pub fn run(scenario: Scenario, fixed_dt: Option<f32>) -> GameResult {
     let cb: ContextBuilder = ContextBuilder::new("Maxwell's Demon Sim", "Ashley Cottrell")
         .window_setup(WindowSetup::default().title("Maxwell's Demon Simulation"))
         .window_mode(WindowMode::default().dimensions(2400.0, 1600.0));
     let (mut ctx, event_loop) = cb.build()?;
-    let state: MainState = MainState::new(&mut ctx, num, law)?;
+    let state: MainState = MainState::new(&mut ctx, scenario, fixed_dt)?;
     event::run(ctx, event_loop, state)
 }